@@ -0,0 +1,279 @@
+//! Code generation for `#[builder(cli)]`: `from_args` fills a builder from
+//! command-line flags named after its fields, and `HELP` describes them.
+
+use crate::options::StructOptions;
+use crate::target::{BuilderField, Target};
+use crate::{
+    alloc, collection_for, inner_for_option, is_required, setter_ty, store_value, Collection,
+};
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Attribute, DeriveInput, Lit, Meta, Type};
+
+/// Generates the associated items of `#[builder(cli)]`, to go in the impl
+/// block of the builder.
+pub(crate) fn expand(
+    input: &DeriveInput,
+    struct_opts: &StructOptions,
+    target: &Target,
+) -> TokenStream {
+    let vis = &input.vis;
+    let error_ty = &target.error_ty;
+    let alloc = alloc(struct_opts);
+    let flags = target
+        .fields
+        .iter()
+        .filter(|f| !f.opts.setter.skip)
+        .map(|f| Flag::new(f, struct_opts))
+        .collect::<Vec<_>>();
+
+    let help = help(&input.attrs, &flags);
+
+    let arm = flags.iter().map(|flag| {
+        let f = flag.field;
+        let name = &f.ident;
+        let long = &flag.long;
+        if flag.switch {
+            let value = parse(&alloc, error_ty, quote!(value), &f.field.ty);
+            let store = store_value(f, error_ty, &alloc, &quote!(__builder));
+            return quote_spanned! {f.field.span()=>
+                #long => {
+                    let #name: bool = match inline.take() {
+                        ::core::option::Option::Some(value) => #value,
+                        ::core::option::Option::None => true,
+                    };
+                    #store
+                }
+            };
+        }
+        let take = quote! {
+            let value = match inline.take().or_else(|| ::core::iter::Iterator::next(&mut args)) {
+                ::core::option::Option::Some(value) => value,
+                ::core::option::Option::None => {
+                    return ::core::result::Result::Err(#error_ty::InvalidArgs(#alloc::format!("missing value for `{}`", flag)))
+                }
+            };
+        };
+        let set = match (&f.opts.each, collection_for(&f.field.ty)) {
+            (Some(_), Some(Collection::Items(item))) => {
+                let item = parse(&alloc, error_ty, quote!(value), item);
+                quote! {
+                    ::core::iter::Extend::extend(
+                        __builder.#name.get_or_insert_with(::core::default::Default::default),
+                        ::core::option::Option::Some(#item),
+                    );
+                }
+            }
+            (Some(_), Some(Collection::Map(key, value))) => {
+                let key = parse(&alloc, error_ty, quote!(key), key);
+                let value = parse(&alloc, error_ty, quote!(value), value);
+                quote! {
+                    let (key, value) = match value.split_once('=') {
+                        ::core::option::Option::Some(pair) => pair,
+                        ::core::option::Option::None => {
+                            return ::core::result::Result::Err(#error_ty::InvalidArgs(#alloc::format!(
+                                "expected `KEY=VALUE` for `{}`",
+                                flag,
+                            )))
+                        }
+                    };
+                    ::core::iter::Extend::extend(
+                        __builder.#name.get_or_insert_with(::core::default::Default::default),
+                        ::core::option::Option::Some((#key, #value)),
+                    );
+                }
+            }
+            _ => {
+                let (ty, wrap) = setter_ty(f);
+                let value = if wrap {
+                    parse(&alloc, error_ty, quote!(value), &ty)
+                } else {
+                    let inner = inner_for_option(&ty).unwrap();
+                    let value = parse(&alloc, error_ty, quote!(value), &inner);
+                    quote!(::core::option::Option::Some(#value))
+                };
+                let store = store_value(f, error_ty, &alloc, &quote!(__builder));
+                quote! {
+                    let #name: #ty = #value;
+                    #store
+                }
+            }
+        };
+        quote_spanned! {f.field.span()=>
+            #long => {
+                #take
+                #set
+            }
+        }
+    });
+
+    quote! {
+        /// Usage of the flags accepted by `from_args`.
+        #vis const HELP: &str = #help;
+
+        /// Fills a builder from command-line flags, without the program name:
+        /// `--field-name value` or `--field-name=value` for every field, repeated
+        /// for collections, and `--field-name` alone to turn on a `bool` field.
+        ///
+        /// Returns `None` as soon as `-h` or `--help` is found, for the caller
+        /// to print `HELP`.
+        #vis fn from_args(
+            args: impl ::core::iter::IntoIterator<Item = #alloc::string::String>,
+        ) -> ::core::result::Result<::core::option::Option<Self>, #error_ty> {
+            let mut __builder: Self = ::core::default::Default::default();
+            let mut args = ::core::iter::IntoIterator::into_iter(args);
+            while let ::core::option::Option::Some(arg) = ::core::iter::Iterator::next(&mut args) {
+                let (flag, mut inline) = match arg.split_once('=') {
+                    ::core::option::Option::Some((flag, value)) if flag.starts_with("--") => {
+                        (
+                            #alloc::string::ToString::to_string(flag),
+                            ::core::option::Option::Some(#alloc::string::ToString::to_string(value)),
+                        )
+                    }
+                    _ => (arg, ::core::option::Option::None),
+                };
+                match flag.as_str() {
+                    "-h" | "--help" => return ::core::result::Result::Ok(::core::option::Option::None),
+                    #(#arm)*
+                    _ => {
+                        return ::core::result::Result::Err(#error_ty::InvalidArgs(#alloc::format!("unexpected argument `{}`", flag)))
+                    }
+                }
+            }
+            ::core::result::Result::Ok(::core::option::Option::Some(__builder))
+        }
+    }
+}
+
+/// The command-line flag filling a field.
+struct Flag<'a> {
+    field: &'a BuilderField<'a>,
+    /// `--current-dir`, or `--arg` for the `each` setter `arg`.
+    long: String,
+    /// Placeholder of the value in the help text: `CURRENT_DIR`.
+    value: String,
+    /// A `bool` field, set by the flag alone.
+    switch: bool,
+    required: bool,
+}
+
+impl<'a> Flag<'a> {
+    fn new(field: &'a BuilderField<'a>, struct_opts: &StructOptions) -> Self {
+        let name = field
+            .opts
+            .each
+            .as_ref()
+            .unwrap_or(&field.ident)
+            .unraw()
+            .to_string();
+        let value = match collection_for(&field.field.ty) {
+            Some(Collection::Map(..)) if field.opts.each.is_some() => String::from("KEY=VALUE"),
+            _ => name.to_uppercase(),
+        };
+        Flag {
+            field,
+            long: format!("--{}", name.replace('_', "-")),
+            value,
+            switch: is_switch(field, struct_opts),
+            required: is_required(field, struct_opts),
+        }
+    }
+}
+
+/// `HELP`: the doc comment of the struct, and a line per flag with the first
+/// line of the field's doc comment.
+fn help(attrs: &[Attribute], flags: &[Flag]) -> String {
+    let mut usage = vec![];
+    for flag in flags {
+        let mut doc = doc_summary(&flag.field.field.attrs);
+        let note = if flag.field.opts.each.is_some() {
+            "repeatable"
+        } else if flag.required {
+            "required"
+        } else {
+            ""
+        };
+        if !note.is_empty() {
+            if !doc.is_empty() {
+                doc.push(' ');
+            }
+            doc.push_str(&format!("({})", note));
+        }
+        let flag = if flag.switch {
+            flag.long.clone()
+        } else {
+            format!("{} <{}>", flag.long, flag.value)
+        };
+        usage.push((flag, doc));
+    }
+    usage.push((
+        String::from("-h, --help"),
+        String::from("Print this help text."),
+    ));
+
+    let width = usage.iter().map(|(flag, _)| flag.len()).max().unwrap_or(0);
+    let mut res = String::new();
+    let about = doc_lines(attrs).join("\n");
+    if !about.is_empty() {
+        res.push_str(&about);
+        res.push_str("\n\n");
+    }
+    res.push_str("Options:\n");
+    for (flag, doc) in usage {
+        let line = format!("  {:width$}  {}", flag, doc, width = width);
+        res.push_str(line.trim_end());
+        res.push('\n');
+    }
+    res
+}
+
+/// The first paragraph of the doc comment in `attrs`, on a single line.
+fn doc_summary(attrs: &[Attribute]) -> String {
+    doc_lines(attrs)
+        .into_iter()
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub(crate) fn doc_lines(attrs: &[Attribute]) -> Vec<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("doc"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::NameValue(nv)) => match nv.lit {
+                Lit::Str(doc) => Some(doc.value().trim().to_owned()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+/// Expression parsing the `&str` expression `value` into a `ty`, returning
+/// from `from_args` on failure.
+fn parse(alloc: &TokenStream, error_ty: &Ident, value: TokenStream, ty: &Type) -> TokenStream {
+    quote! {
+        match #value.parse::<#ty>() {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(err) => {
+                return ::core::result::Result::Err(#error_ty::InvalidArgs(#alloc::format!("invalid value for `{}`: {}", flag, err)))
+            }
+        }
+    }
+}
+
+/// A `bool` field of a `cli` builder, turned on by its flag alone. Left unset,
+/// it is off rather than missing.
+pub(crate) fn is_switch(f: &BuilderField, struct_opts: &StructOptions) -> bool {
+    struct_opts.cli && f.opts.each.is_none() && is_bool(&f.field.ty)
+}
+
+fn is_bool(ty: &Type) -> bool {
+    match ty {
+        Type::Path(ty) => ty.qself.is_none() && ty.path.is_ident("bool"),
+        _ => false,
+    }
+}
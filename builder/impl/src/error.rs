@@ -0,0 +1,183 @@
+//! The error type returned by the generated `build()`.
+
+use crate::alloc;
+use crate::options::StructOptions;
+use crate::target::Target;
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::Visibility;
+
+/// Generates `enum #error_ty` alongside its `Display` and `Error` impls.
+pub(crate) fn expand(
+    struct_opts: &StructOptions,
+    vis: &Visibility,
+    target: &Target,
+) -> TokenStream {
+    let Target {
+        builder_ty,
+        error_ty,
+        build_fn,
+        ..
+    } = target;
+    let alloc = alloc(struct_opts);
+    // `core::error::Error` is the same trait, but only since Rust 1.81.
+    let error_trait = if struct_opts.no_std {
+        quote!(::core::error::Error)
+    } else {
+        quote!(::std::error::Error)
+    };
+    let doc = format!("Error returned by [`{}::{}`].", builder_ty, build_fn);
+    // Only `from_args` returns `InvalidArgs`, never `build()`. `cli` builders
+    // have no sub-builders, so there is no such variant to nest it as.
+    let (cli_variant, cli_display, cli_nest) = if struct_opts.cli {
+        (
+            quote! {
+                /// Command-line flags passed to `from_args` were not understood,
+                /// with the reason.
+                InvalidArgs(#alloc::string::String),
+            },
+            quote! {
+                #error_ty::InvalidArgs(reason) => ::core::write!(f, "invalid arguments: {}", reason),
+            },
+            quote! {
+                #error_ty::InvalidArgs(reason) => {
+                    __E::validation(#alloc::format!("{}: invalid arguments: {}", prefix, reason))
+                }
+            },
+        )
+    } else {
+        (quote!(), quote!(), quote!())
+    };
+    quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        #[non_exhaustive]
+        #vis enum #error_ty {
+            /// Required fields which were not set, in declaration order.
+            MissingFields(#alloc::vec::Vec<#alloc::string::String>),
+            /// The assembled value was rejected, with the reason.
+            Validation(#alloc::string::String),
+            /// The value given to the setter of a field was rejected by its
+            /// validator, or could not be converted by its `try_` setter.
+            InvalidValue {
+                field: #alloc::string::String,
+                reason: #alloc::string::String,
+            },
+            /// The environment variable read for an unset field could not be
+            /// parsed into its type.
+            InvalidEnvVar {
+                field: #alloc::string::String,
+                var: #alloc::string::String,
+                reason: #alloc::string::String,
+            },
+            /// A `set_once` field was given a value while it already had one.
+            SetTwice(#alloc::string::String),
+            /// `field` was set without the field it requires.
+            Requires {
+                field: #alloc::string::String,
+                required: #alloc::string::String,
+            },
+            /// `field` was set along with a field it conflicts with.
+            Conflicts {
+                field: #alloc::string::String,
+                other: #alloc::string::String,
+            },
+            #cli_variant
+        }
+
+        impl ::core::fmt::Display for #error_ty {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #error_ty::MissingFields(fields) => {
+                        f.write_str("missing required fields: ")?;
+                        for (i, field) in ::core::iter::Iterator::enumerate(fields.iter()) {
+                            if i > 0 {
+                                f.write_str(", ")?;
+                            }
+                            ::core::write!(f, "`{}`", field)?;
+                        }
+                        ::core::result::Result::Ok(())
+                    }
+                    #error_ty::Validation(reason) => ::core::write!(f, "validation failed: {}", reason),
+                    #error_ty::InvalidValue { field, reason } => {
+                        ::core::write!(f, "invalid value for `{}`: {}", field, reason)
+                    }
+                    #error_ty::InvalidEnvVar { field, var, reason } => {
+                        ::core::write!(f, "invalid `{}` from environment variable `{}`: {}", field, var, reason)
+                    }
+                    #error_ty::SetTwice(field) => ::core::write!(f, "`{}` was set more than once", field),
+                    #error_ty::Requires { field, required } => {
+                        ::core::write!(f, "`{}` requires `{}` to be set", field, required)
+                    }
+                    #error_ty::Conflicts { field, other } => {
+                        ::core::write!(f, "`{}` conflicts with `{}`, they cannot both be set", field, other)
+                    }
+                    #cli_display
+                }
+            }
+        }
+
+        impl #error_trait for #error_ty {}
+
+        impl ::derive_builder::__private::NestedError for #error_ty {
+            fn nest<__E: ::derive_builder::__private::NestedError>(self, prefix: &str) -> __E {
+                let nested = |field: #alloc::string::String| #alloc::format!("{}.{}", prefix, field);
+                match self {
+                    #error_ty::MissingFields(fields) => {
+                        let fields = ::core::iter::IntoIterator::into_iter(fields);
+                        __E::missing_fields(::core::iter::Iterator::collect(::core::iter::Iterator::map(fields, nested)))
+                    }
+                    #error_ty::Validation(reason) => {
+                        __E::validation(#alloc::format!("{}: {}", prefix, reason))
+                    }
+                    #error_ty::InvalidValue { field, reason } => __E::invalid_value(nested(field), reason),
+                    #error_ty::InvalidEnvVar { field, var, reason } => {
+                        __E::invalid_env_var(nested(field), var, reason)
+                    }
+                    #error_ty::SetTwice(field) => __E::set_twice(nested(field)),
+                    #error_ty::Requires { field, required } => __E::requires(nested(field), nested(required)),
+                    #error_ty::Conflicts { field, other } => __E::conflicts(nested(field), nested(other)),
+                    #cli_nest
+                }
+            }
+
+            fn missing_fields(fields: #alloc::vec::Vec<#alloc::string::String>) -> Self {
+                #error_ty::MissingFields(fields)
+            }
+
+            fn validation(reason: #alloc::string::String) -> Self {
+                #error_ty::Validation(reason)
+            }
+
+            fn invalid_value(field: #alloc::string::String, reason: #alloc::string::String) -> Self {
+                #error_ty::InvalidValue { field, reason }
+            }
+
+            fn invalid_env_var(
+                field: #alloc::string::String,
+                var: #alloc::string::String,
+                reason: #alloc::string::String,
+            ) -> Self {
+                #error_ty::InvalidEnvVar { field, var, reason }
+            }
+
+            fn set_twice(field: #alloc::string::String) -> Self {
+                #error_ty::SetTwice(field)
+            }
+
+            fn requires(field: #alloc::string::String, required: #alloc::string::String) -> Self {
+                #error_ty::Requires { field, required }
+            }
+
+            fn conflicts(field: #alloc::string::String, other: #alloc::string::String) -> Self {
+                #error_ty::Conflicts { field, other }
+            }
+        }
+    }
+}
+
+/// Expression turning `err`, the error of the sub-builder of `field`, into the
+/// error of this builder naming the fields through `field`.
+pub(crate) fn nested(field: &str, span: Span) -> TokenStream {
+    quote_spanned!(span=> ::derive_builder::__private::NestedError::nest(err, #field))
+}
@@ -0,0 +1,135 @@
+//! The `FIELDS` table of a builder, describing its fields for code reading
+//! them at runtime, such as documentation generators.
+
+use crate::cli::{doc_lines, is_switch};
+use crate::is_required;
+use crate::options::{FieldDefault, StructOptions};
+use crate::target::Target;
+use proc_macro2::{Delimiter, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::DeriveInput;
+
+/// Generates `const FIELDS`, to go in the impl block of the builder.
+pub(crate) fn expand(
+    input: &DeriveInput,
+    struct_opts: &StructOptions,
+    target: &Target,
+) -> TokenStream {
+    let vis = &input.vis;
+    let info = target.fields.iter().map(|f| {
+        let name = f.ident.unraw().to_string();
+        let ty = source(&f.field.ty);
+        let kind = if f.opts.sub_builder {
+            quote!(SubBuilder)
+        } else if f.opts.each.is_some() {
+            quote!(Each)
+        } else if is_required(f, struct_opts) {
+            quote!(Required)
+        } else {
+            quote!(Optional)
+        };
+        let default = match &f.opts.default {
+            Some(FieldDefault::Trait) => Some(String::from("Default::default()")),
+            Some(FieldDefault::Expr(expr)) => Some(source(expr)),
+            None if struct_opts.default => {
+                let member = &f.member;
+                Some(format!("{}::default().{}", input.ident, quote!(#member)))
+            }
+            None if is_switch(f, struct_opts) => Some(String::from("false")),
+            None => None,
+        };
+        let default = match default {
+            Some(default) => quote!(::core::option::Option::Some(#default)),
+            None => quote!(::core::option::Option::None),
+        };
+        let doc = doc_lines(&f.field.attrs).join("\n");
+        quote! {
+            ::derive_builder::BuilderFieldInfo {
+                name: #name,
+                ty: #ty,
+                kind: ::derive_builder::BuilderFieldKind::#kind,
+                default: #default,
+                doc: #doc,
+            }
+        }
+    });
+    quote! {
+        /// Every field of the builder, in declaration order.
+        #vis const FIELDS: &'static [::derive_builder::BuilderFieldInfo] = &[#(#info),*];
+    }
+}
+
+/// `tokens` as they would be written: `Vec<String>` rather than the
+/// `Vec < String >` of `to_string()`. The lifetimes `#[builder]` gives to the
+/// parameters of a function are left out, as in its signature.
+fn source(tokens: &impl ToTokens) -> String {
+    let mut res = String::new();
+    write_source(&mut res, tokens.to_token_stream());
+    res
+}
+
+fn write_source(res: &mut String, tokens: TokenStream) {
+    let mut tokens = tokens.into_iter().peekable();
+    // Two words in a row need a space between them: `&'a str`, `dyn Error`.
+    let mut after_word = false;
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(_) | TokenTree::Literal(_) => {
+                if after_word {
+                    res.push(' ');
+                }
+                res.push_str(&token.to_string());
+                after_word = true;
+            }
+            TokenTree::Punct(punct) => {
+                let c = punct.as_char();
+                let named = match tokens.peek() {
+                    Some(TokenTree::Ident(ident)) if c == '\'' => {
+                        ident.to_string().starts_with("__builder")
+                    }
+                    _ => false,
+                };
+                if named {
+                    tokens.next();
+                    if let Some(TokenTree::Punct(punct)) = tokens.peek() {
+                        if punct.as_char() == ',' {
+                            tokens.next();
+                        }
+                    }
+                    continue;
+                }
+                match c {
+                    ',' | ';' => {
+                        res.push(c);
+                        res.push(' ');
+                    }
+                    '=' | '+' if punct.spacing() == Spacing::Alone => {
+                        res.push(' ');
+                        res.push(c);
+                        res.push(' ');
+                    }
+                    '-' if punct.spacing() == Spacing::Joint => res.push_str(" -"),
+                    '>' if res.ends_with(" -") => res.push_str("> "),
+                    _ => res.push(c),
+                }
+                after_word = false;
+            }
+            TokenTree::Group(group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::Brace => (" { ", " }"),
+                    Delimiter::None => ("", ""),
+                };
+                res.push_str(open);
+                write_source(res, group.stream());
+                if res.ends_with(' ') {
+                    res.pop();
+                }
+                res.push_str(close);
+                after_word = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! `#[builder]` on a function: its parameters become the fields of a builder
+//! whose `call()` passes them to the function, `connect_builder().host(..)
+//! .port(..).call()` for `fn connect(host: &str, port: u16)`.
+//!
+//! The fields are collected into a struct that is never emitted, and go
+//! through the same code generation as a derive on it.
+//!
+//! On an `impl` block, the functions marked `#[builder]` get theirs outside of
+//! the block, `cache.get_builder().key(..).call()` for `fn get(&self, key:
+//! &str)` in `impl Cache`. The builder of a method holds its receiver.
+
+use crate::options::{Pattern, StructOptions};
+use crate::target::{Call, InImpl, Target};
+use crate::typestate::camel_case;
+use crate::{expand_builder, type_params, typestate};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use syn::ext::IdentExt;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_quote, Attribute, Data, DeriveInput, FnArg, GenericParam, Generics, ImplItem, ItemFn,
+    ItemImpl, Lifetime, LifetimeDef, Meta, NestedMeta, ParenthesizedGenericArguments, Pat,
+    ReturnType, Token, Type, TypeBareFn, TypeImplTrait, TypeReference,
+};
+
+/// The `impl` block of the functions `#[builder]` on the block generates
+/// builders for.
+pub(crate) struct ImplBlock {
+    self_ty: Type,
+    generics: Generics,
+}
+
+/// Generates the builders of the functions of `item` marked `#[builder]`,
+/// taking that attribute off them.
+pub(crate) fn expand_impl(args: TokenStream, item: &mut ItemImpl) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(
+            args,
+            "options go on the `#[builder]` of each function",
+        ));
+    }
+    if let Some((_, path, _)) = &item.trait_ {
+        return Err(syn::Error::new_spanned(
+            path,
+            "the functions of a trait impl are called through the trait, `#[builder]` only supports inherent `impl` blocks",
+        ));
+    }
+    let block = ImplBlock {
+        self_ty: (*item.self_ty).clone(),
+        generics: item.generics.clone(),
+    };
+    let mut builders = TokenStream::new();
+    let mut found = false;
+    for impl_item in &mut item.items {
+        let method = match impl_item {
+            ImplItem::Method(method) => method,
+            _ => continue,
+        };
+        let (attrs, others) = method
+            .attrs
+            .drain(..)
+            .partition::<Vec<_>, _>(|attr| attr.path.is_ident("builder"));
+        method.attrs = others;
+        let attr = match attrs.as_slice() {
+            [] => continue,
+            [attr] => attr,
+            [_, attr, ..] => {
+                builders.extend(
+                    syn::Error::new_spanned(attr, "duplicate `#[builder]`").into_compile_error(),
+                );
+                continue;
+            }
+        };
+        found = true;
+        let args = match attr.parse_meta() {
+            Ok(Meta::Path(_)) => Ok(TokenStream::new()),
+            Ok(Meta::List(list)) => Ok(list.nested.into_token_stream()),
+            Ok(meta) => Err(syn::Error::new_spanned(
+                meta,
+                "expected `#[builder]` or `#[builder(...)]`",
+            )),
+            Err(err) => Err(err),
+        };
+        let mut function = ItemFn {
+            attrs: method.attrs.clone(),
+            vis: method.vis.clone(),
+            sig: method.sig.clone(),
+            block: Box::new(method.block.clone()),
+        };
+        let builder = args.and_then(|args| expand(args, &mut function, Some(&block)));
+        method.sig = function.sig;
+        // As with a single function, the others keep their builders.
+        builders.extend(builder.unwrap_or_else(syn::Error::into_compile_error));
+    }
+    if !found {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "mark the functions to generate builders for with `#[builder]`",
+        ));
+    }
+    Ok(builders)
+}
+
+/// Generates the builder of `item`, taking the builder's attributes off its
+/// parameters, where they would be rejected. A function of an `impl` block
+/// gets one outside of the `block`, with `Self` spelled out.
+pub(crate) fn expand(
+    args: TokenStream,
+    item: &mut ItemFn,
+    block: Option<&ImplBlock>,
+) -> syn::Result<TokenStream> {
+    let param_attrs = item
+        .sig
+        .inputs
+        .iter_mut()
+        .map(|input| match input {
+            FnArg::Typed(input) => {
+                let (attrs, others) = input
+                    .attrs
+                    .drain(..)
+                    .partition::<Vec<_>, _>(|attr| attr.path.is_ident("builder"));
+                input.attrs = others;
+                attrs
+            }
+            FnArg::Receiver(_) => vec![],
+        })
+        .collect::<Vec<_>>();
+    let mut sig = item.sig.clone();
+    if let Some(block) = block {
+        ReplaceSelf {
+            self_ty: &block.self_ty,
+        }
+        .visit_signature_mut(&mut sig);
+    }
+    let sig = &sig;
+    if let (None, Some(receiver)) = (block, sig.receiver()) {
+        return Err(syn::Error::new_spanned(
+            receiver,
+            "the builder of a method goes next to its `impl` block, put `#[builder]` on the block as well",
+        ));
+    }
+    let struct_opts = struct_options(args, sig.receiver().is_some())?;
+    if let Some(variadic) = &sig.variadic {
+        return Err(syn::Error::new_spanned(
+            variadic,
+            "variadic functions cannot have builders",
+        ));
+    }
+
+    let mut generics = sig.generics.clone();
+    let mut elided = NameElided::default();
+    let mut fields = vec![];
+    let mut receiver = None;
+    for (input, attrs) in sig.inputs.iter().zip(param_attrs) {
+        let input = match (input, block) {
+            (FnArg::Receiver(input), Some(block)) => {
+                let mut ty = match &input.reference {
+                    Some((and, lifetime)) => {
+                        let mutability = &input.mutability;
+                        let self_ty = &block.self_ty;
+                        parse_quote!(#and #lifetime #mutability #self_ty)
+                    }
+                    None => block.self_ty.clone(),
+                };
+                elided.visit_type_mut(&mut ty);
+                receiver = Some(ty);
+                continue;
+            }
+            (FnArg::Typed(input), Some(_)) if is_self(&input.pat) => {
+                let mut ty = (*input.ty).clone();
+                elided.visit_type_mut(&mut ty);
+                receiver = Some(ty);
+                continue;
+            }
+            (FnArg::Typed(input), _) => input,
+            (FnArg::Receiver(_), None) => unreachable!(),
+        };
+        let ident = match &*input.pat {
+            Pat::Ident(pat) if pat.by_ref.is_none() && pat.subpat.is_none() => pat.ident.clone(),
+            pat => {
+                return Err(syn::Error::new_spanned(
+                    pat,
+                    "expected a named parameter, which names its setter",
+                ))
+            }
+        };
+        let mut ty = (*input.ty).clone();
+        elided.visit_type_mut(&mut ty);
+        if let Some(err) = elided.err.take() {
+            return Err(err);
+        }
+        fields.push(quote!(#(#attrs)* #ident: #ty));
+    }
+    let lifetimes = generics.lifetimes().count();
+    for (i, lifetime) in elided.named.iter().enumerate() {
+        let param = GenericParam::Lifetime(LifetimeDef::new(lifetime.clone()));
+        generics.params.insert(lifetimes + i, param);
+    }
+
+    // As with lifetime elision, an elided lifetime in the output is the one
+    // of the only lifetime the parameters have.
+    let output = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => {
+            let mut ty = (**ty).clone();
+            let mut lifetimes = generics.lifetimes().map(|param| &param.lifetime);
+            let only = match (lifetimes.next(), lifetimes.next()) {
+                (Some(lifetime), None) => Some(lifetime.clone()),
+                _ => None,
+            };
+            // A borrowed receiver lends its lifetime instead.
+            let only = match &receiver {
+                Some(Type::Reference(receiver)) => receiver.lifetime.clone(),
+                _ => only,
+            };
+            let mut elided = NameElided {
+                output: true,
+                only,
+                ..NameElided::default()
+            };
+            elided.visit_type_mut(&mut ty);
+            if let Some(err) = elided.err {
+                return Err(err);
+            }
+            quote!(#ty)
+        }
+    };
+
+    let docs = item.attrs.iter().filter(|attr| attr.path.is_ident("doc"));
+    let vis = &item.vis;
+    let function = camel_case(&sig.ident);
+    let ident = match block.map(|block| &block.self_ty) {
+        None => function,
+        Some(Type::Path(ty)) if ty.qself.is_none() => {
+            let ty = &ty.path.segments.last().unwrap().ident;
+            format!("{}{}", ty.unraw(), function)
+        }
+        Some(_) if struct_opts.name.is_some() => function,
+        Some(ty) => {
+            return Err(syn::Error::new_spanned(
+                ty,
+                r#"the builder is named after the type of the `impl` block, which has no name, name it with `#[builder(name = "...")]`"#,
+            ))
+        }
+    };
+    let ident = Ident::new(&ident, sig.ident.span());
+    // The builder is generic over the parameters of the `impl` block as well.
+    let struct_generics = match block {
+        Some(block) => merge_generics(&block.generics, &generics),
+        None => generics.clone(),
+    };
+    let where_clause = &struct_generics.where_clause;
+    let input: DeriveInput = parse_quote! {
+        #(#docs)*
+        #vis struct #ident #struct_generics #where_clause {
+            #(#fields,)*
+        }
+    };
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => unreachable!(),
+    };
+
+    let mut target = Target::for_struct(&input, &struct_opts, fields)?;
+    target.constructor = match &struct_opts.constructor {
+        Some(constructor) => constructor.clone(),
+        None => format_ident!("{}_builder", sig.ident.unraw(), span = sig.ident.span()),
+    };
+    let function = &sig.ident;
+    let params = type_params(&sig.generics);
+    let asyncness = &sig.asyncness;
+    let unsafety = &sig.unsafety;
+    let function = match block {
+        Some(block) => {
+            let self_ty = &block.self_ty;
+            quote!(<#self_ty>::#function)
+        }
+        None => quote!(#function),
+    };
+    target.call = Some(Call {
+        function: if params.is_empty() {
+            function
+        } else {
+            quote!(#function::<#(#params),*>)
+        },
+        qualifiers: quote!(#asyncness #unsafety),
+        output,
+        is_async: asyncness.is_some(),
+        in_impl: block.map(|block| {
+            let self_ty = &block.self_ty;
+            let (impl_generics, _, where_clause) = block.generics.split_for_impl();
+            InImpl {
+                header: quote!(impl #impl_generics #self_ty #where_clause),
+                self_ty: self_ty.clone(),
+                generics,
+                receiver,
+            }
+        }),
+    });
+
+    let builder = if struct_opts.typestate {
+        typestate::expand(&input, &struct_opts, &target)
+    } else {
+        expand_builder(&input, &struct_opts, &target)
+    };
+    Ok(builder)
+}
+
+/// The options given as `#[builder(...)]`, which are those of the derive
+/// besides the ones needing a struct. `build()` is named `call()`.
+///
+/// The builder of a method holds its `receiver` until `call()` passes it on,
+/// so it is not `Default` and always uses the owned pattern.
+fn struct_options(args: TokenStream, receiver: bool) -> syn::Result<StructOptions> {
+    let attr: Attribute = parse_quote!(#[builder(#args)]);
+    let mut struct_opts = StructOptions::from_attrs(&[attr])?;
+    let metas =
+        syn::parse::Parser::parse2(Punctuated::<NestedMeta, Token![,]>::parse_terminated, args)?;
+    let find = |key| {
+        metas.iter().find(|nested| match nested {
+            NestedMeta::Meta(meta) => meta.path().is_ident(key),
+            NestedMeta::Lit(_) => false,
+        })
+    };
+    if struct_opts.default {
+        return Err(syn::Error::new_spanned(
+            find("default").unwrap(),
+            "`builder(default)` needs a struct to take the default values from",
+        ));
+    }
+    if struct_opts.build_fn.validate.is_some() {
+        return Err(syn::Error::new_spanned(
+            find("build_fn").unwrap(),
+            "the arguments of a function are not a value to validate, the function can check them",
+        ));
+    }
+    if receiver {
+        match find("pattern") {
+            Some(pattern) if struct_opts.pattern != Pattern::Owned => {
+                return Err(syn::Error::new_spanned(
+                    pattern,
+                    "the builder of a method moves its receiver into the call, so it always uses the owned pattern",
+                ))
+            }
+            _ => struct_opts.pattern = Pattern::Owned,
+        }
+        for key in &["cli", "serde"] {
+            if let Some(option) = find(key) {
+                return Err(syn::Error::new_spanned(
+                    option,
+                    format!(
+                        "`{}` starts from an empty builder, which that of a method is not, as it holds the receiver",
+                        key,
+                    ),
+                ));
+            }
+        }
+    }
+    struct_opts
+        .build_fn
+        .name
+        .get_or_insert_with(|| format_ident!("call"));
+    Ok(struct_opts)
+}
+
+/// The parameters of an `impl` block followed by those of one of its
+/// functions, lifetimes first.
+fn merge_generics(block: &Generics, function: &Generics) -> Generics {
+    let lifetimes = block.lifetimes().chain(function.lifetimes());
+    let others = block
+        .params
+        .iter()
+        .chain(&function.params)
+        .filter(|param| !matches!(param, GenericParam::Lifetime(_)));
+    let mut res = function.clone();
+    res.params = lifetimes
+        .cloned()
+        .map(GenericParam::Lifetime)
+        .chain(others.cloned())
+        .collect();
+    if let Some(block) = &block.where_clause {
+        res.make_where_clause()
+            .predicates
+            .extend(block.predicates.iter().cloned());
+    }
+    res
+}
+
+/// The parameter is the receiver written as `self: Box<Self>`.
+fn is_self(pat: &Pat) -> bool {
+    matches!(pat, Pat::Ident(pat) if pat.ident == "self")
+}
+
+/// Replaces `Self` in the signature of a function of an `impl` block, which
+/// the builder outside of the block cannot name, with the block's type.
+struct ReplaceSelf<'a> {
+    self_ty: &'a Type,
+}
+
+impl VisitMut for ReplaceSelf<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        if let Type::Path(path) = ty {
+            if path.qself.is_none() && path.path.segments[0].ident == "Self" {
+                let self_ty = self.self_ty;
+                let rest = path.path.segments.iter().skip(1).collect::<Vec<_>>();
+                *ty = if rest.is_empty() {
+                    self_ty.clone()
+                } else {
+                    parse_quote!(<#self_ty>::#(#rest)::*)
+                };
+                return;
+            }
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+/// Names the elided lifetimes of a parameter's type, which a field cannot
+/// leave out: `&str` becomes `&'__builder0 str`. In the function's output,
+/// they are all the `only` lifetime of the parameters instead.
+#[derive(Default)]
+struct NameElided {
+    output: bool,
+    only: Option<Lifetime>,
+    /// The lifetimes named so far, to declare on the builder.
+    named: Vec<Lifetime>,
+    err: Option<syn::Error>,
+}
+
+impl NameElided {
+    fn name(&mut self, span: Span) -> Option<Lifetime> {
+        if self.output {
+            if self.only.is_none() {
+                self.fail(
+                    span,
+                    "the parameters do not have a single lifetime for the return type to borrow from, name it",
+                );
+            }
+            return self.only.clone();
+        }
+        let lifetime = Lifetime::new(&format!("'__builder{}", self.named.len()), span);
+        self.named.push(lifetime.clone());
+        Some(lifetime)
+    }
+
+    fn fail(&mut self, span: Span, message: &str) {
+        self.err
+            .get_or_insert_with(|| syn::Error::new(span, message));
+    }
+}
+
+impl VisitMut for NameElided {
+    fn visit_type_reference_mut(&mut self, ty: &mut TypeReference) {
+        if ty.lifetime.is_none() {
+            ty.lifetime = self.name(ty.and_token.span);
+        }
+        visit_mut::visit_type_reference_mut(self, ty);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        if lifetime.ident == "_" {
+            if let Some(named) = self.name(lifetime.span()) {
+                *lifetime = named;
+            }
+        }
+    }
+
+    fn visit_type_impl_trait_mut(&mut self, ty: &mut TypeImplTrait) {
+        self.err.get_or_insert_with(|| {
+            syn::Error::new_spanned(
+                ty,
+                "an `impl Trait` parameter cannot be stored in the builder, use a type parameter instead",
+            )
+        });
+    }
+
+    // Lifetimes elided within `fn(&str)` and `Fn(&str)` are those of the call,
+    // not of the parameter.
+    fn visit_type_bare_fn_mut(&mut self, _: &mut TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut ParenthesizedGenericArguments) {
+    }
+}
@@ -0,0 +1,1480 @@
+extern crate proc_macro;
+
+mod cli;
+mod error;
+mod fields;
+mod function;
+mod options;
+mod target;
+mod typestate;
+
+use options::{FieldDefault, Pattern, StructOptions};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
+use target::{BuilderField, Call, Target};
+
+#[proc_macro_derive(Builder, attributes(builder))]
+pub fn derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    // Parse the input tokens into a syntax tree.
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let expanded = expand(input).unwrap_or_else(syn::Error::into_compile_error);
+
+    // eprintln!("{:#?}", expanded);
+
+    // Hand the output tokens back to the compiler.
+    proc_macro::TokenStream::from(expanded)
+}
+
+/// Generates a builder collecting the arguments of a function, with the
+/// options of the derive, `connect_builder().host(..).port(..).call()` for
+/// `fn connect(host: &str, port: u16)`. On an `impl` block, generates those of
+/// its methods and associated functions marked `#[builder]`.
+#[proc_macro_attribute]
+pub fn builder(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let mut item = parse_macro_input!(input as syn::Item);
+
+    // The functions stay usable when their builders cannot be generated.
+    let builder = match &mut item {
+        syn::Item::Fn(item) => function::expand(args.into(), item, None),
+        syn::Item::Impl(item) => function::expand_impl(args.into(), item),
+        _ => Err(syn::Error::new(
+            Span::call_site(),
+            "`#[builder]` goes on a function, or on an `impl` block",
+        )),
+    }
+    .unwrap_or_else(syn::Error::into_compile_error);
+    let expanded = quote! {
+        #item
+        #builder
+    };
+
+    proc_macro::TokenStream::from(expanded)
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream> {
+    let struct_opts = StructOptions::from_attrs(&input.attrs)?;
+
+    let targets = match &input.data {
+        Data::Struct(data) => vec![Target::for_struct(&input, &struct_opts, &data.fields)?],
+        Data::Enum(data) => {
+            if struct_opts.default {
+                return Err(syn::Error::new_spanned(
+                    &input.ident,
+                    "`builder(default)` needs a struct to take the default values from",
+                ));
+            }
+            // Unit variants have nothing to build.
+            data.variants
+                .iter()
+                .filter(|variant| !matches!(variant.fields, Fields::Unit))
+                .map(|variant| Target::for_variant(&input, &struct_opts, variant))
+                .collect::<syn::Result<Vec<_>>>()?
+        }
+        Data::Union(data) => {
+            return Err(syn::Error::new_spanned(
+                data.union_token,
+                "`Builder` cannot be derived for unions",
+            ))
+        }
+    };
+
+    let mut expanded = TokenStream::new();
+    for target in &targets {
+        expanded.extend(if struct_opts.typestate {
+            typestate::expand(&input, &struct_opts, target)
+        } else {
+            expand_builder(&input, &struct_opts, target)
+        });
+    }
+    Ok(expanded)
+}
+
+fn expand_builder(
+    input: &DeriveInput,
+    struct_opts: &StructOptions,
+    target: &Target,
+) -> TokenStream {
+    // Used in the quasi-quotation below as `#name`.
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let Target {
+        builder_ty,
+        error_ty,
+        build_fn,
+        fields,
+        ..
+    } = target;
+    let alloc = alloc(struct_opts);
+
+    let builder_fields = {
+        let builder_field = fields.iter().map(|f| {
+            let name = &field_ident(f);
+            if f.opts.sub_builder {
+                let sub_builder = sub_builder_ty(&f.field.ty).unwrap();
+                return quote_spanned! {f.field.ty.span()=>
+                    #name: #sub_builder,
+                };
+            }
+            let ty = &inner_for_option(&f.field.ty).unwrap_or_else(|| f.field.ty.clone());
+            quote_spanned! {f.field.span()=>
+                #name: ::core::option::Option<#ty>,
+            }
+        });
+        let marker = phantom_marker(generics);
+        let skip = if struct_opts.serde {
+            quote!(#[serde(skip)])
+        } else {
+            quote!()
+        };
+        let set_twice = if target.tracks_set_twice() {
+            quote! {
+                #skip
+                __set_twice: ::core::option::Option<&'static str>,
+            }
+        } else {
+            quote!()
+        };
+        let receiver = target.receiver().map(|ty| quote!(__receiver: #ty,));
+        quote! {
+            #receiver
+            #(#builder_field)*
+            #set_twice
+            #skip
+            __marker: ::core::marker::PhantomData<#marker>,
+        }
+    };
+
+    // Built with the receiver `receiver`, for a method's builder.
+    let builder_init = |receiver: TokenStream| {
+        let receiver = target.receiver().map(|_| quote!(__receiver: #receiver,));
+        let init_field = fields.iter().map(|f| {
+            let name = &field_ident(f);
+            if f.opts.sub_builder {
+                quote_spanned! {f.field.ty.span()=>
+                    #name: ::core::default::Default::default(),
+                }
+            } else {
+                quote_spanned! {f.field.span()=>
+                    #name: ::core::option::Option::None,
+                }
+            }
+        });
+        let set_twice = set_twice_init(target);
+        quote! {
+            #builder_ty {
+                #receiver
+                #(#init_field)*
+                #set_twice
+                __marker: ::core::marker::PhantomData,
+            }
+        }
+    };
+
+    // Pushes the field onto `missing` when `unset`, or the fields missing from
+    // a sub-builder, named through the field.
+    let push_missing = |f: &BuilderField, unset: TokenStream| {
+        let name = &field_ident(f);
+        let name_str = name.unraw().to_string();
+        if f.opts.sub_builder {
+            quote_spanned! {f.field.ty.span()=>
+                for field in self.#name.missing_fields() {
+                    missing.push(#alloc::format!("{}.{}", #name_str, field));
+                }
+            }
+        } else {
+            quote_spanned! {f.field.span()=>
+                if #unset {
+                    missing.push(#alloc::string::ToString::to_string(#name_str));
+                }
+            }
+        }
+    };
+
+    let builder_impl = {
+        // The body of `build()` for `pattern`.
+        let build_fn_body = |pattern: Pattern| {
+            let check = fields
+                .iter()
+                .filter(|f| is_required(f, struct_opts) || f.opts.sub_builder)
+                .map(|f| {
+                    let name = &f.ident;
+                    let unset = match &f.opts.env {
+                        Some(_) => {
+                            let env = env_local(f);
+                            quote!(self.#name.is_none() && #env.is_none())
+                        }
+                        None => quote!(self.#name.is_none()),
+                    };
+                    push_missing(f, unset)
+                });
+            // Only read when the field is unset, before checking for missing
+            // fields since a variable may provide one.
+            let env = fields.iter().filter(|f| f.opts.env.is_some()).map(|f| {
+                let name = &f.ident;
+                let var = f.opts.env.as_ref().unwrap();
+                let ty = inner_for_option(&f.field.ty).unwrap_or_else(|| f.field.ty.clone());
+                let env = env_local(f);
+                let field = name.unraw().to_string();
+                let invalid = quote! {
+                    return ::core::result::Result::Err(#error_ty::InvalidEnvVar {
+                        field: #alloc::string::ToString::to_string(#field),
+                        var: #alloc::string::ToString::to_string(#var),
+                        reason: #alloc::string::ToString::to_string(&err),
+                    })
+                };
+                // Checked like a value given to the setter, which takes an
+                // `Option` unless it strips it.
+                let parsed = match &f.opts.validate {
+                    Some(validate) if setter_ty(f).1 => quote_spanned! {validate.span()=>
+                        if let ::core::result::Result::Err(err) = #validate(&value) {
+                            #invalid
+                        }
+                        ::core::option::Option::Some(value)
+                    },
+                    Some(validate) => quote_spanned! {validate.span()=>
+                        let value = ::core::option::Option::Some(value);
+                        if let ::core::result::Result::Err(err) = #validate(&value) {
+                            #invalid
+                        }
+                        value
+                    },
+                    None => quote!(::core::option::Option::Some(value)),
+                };
+                quote_spanned! {f.field.span()=>
+                    let #env = if self.#name.is_some() {
+                        ::core::option::Option::None
+                    } else {
+                        match ::std::env::var(#var) {
+                            ::core::result::Result::Ok(value) => match value.parse::<#ty>() {
+                                ::core::result::Result::Ok(value) => {
+                                    #parsed
+                                }
+                                ::core::result::Result::Err(err) => {
+                                    #invalid
+                                }
+                            },
+                            ::core::result::Result::Err(::std::env::VarError::NotPresent) => ::core::option::Option::None,
+                            ::core::result::Result::Err(err) => {
+                                #invalid
+                            }
+                        }
+                    };
+                }
+            });
+            // Built before taking anything out of `self`, so that an error leaves
+            // the builder as it was, through `__build()` whatever the pattern of
+            // the sub-builder. Those of a mutable builder empty themselves, and
+            // are given back what they built if a later one fails, into the
+            // fields which were set.
+            let sub_builders = fields
+                .iter()
+                .filter(|f| f.opts.sub_builder)
+                .collect::<Vec<_>>();
+            let sub_build = sub_builders.iter().enumerate().map(|(i, f)| {
+                let name = &field_ident(f);
+                let built = match pattern {
+                    Pattern::Mutable => quote!(self.#name.__build()),
+                    Pattern::Owned => quote!({ self.#name }.__build()),
+                    Pattern::Immutable => {
+                        quote!(::core::clone::Clone::clone(&self.#name).__build())
+                    }
+                };
+                let (was_set, restore) = match pattern {
+                    Pattern::Mutable => {
+                        let was_set = was_set_local(f);
+                        let restore = sub_builders[..i].iter().map(|f| {
+                            let name = &field_ident(f);
+                            let was_set = was_set_local(f);
+                            quote_spanned!(f.field.ty.span()=> self.#name.__restore(#name, &mut #was_set.iter());)
+                        });
+                        (
+                            quote_spanned!(f.field.ty.span()=> let #was_set = self.#name.__was_set();),
+                            quote!(#(#restore)*),
+                        )
+                    }
+                    _ => (quote!(), quote!()),
+                };
+                let nested = error::nested(&name.unraw().to_string(), f.field.ty.span());
+                quote_spanned! {f.field.ty.span()=>
+                    #was_set
+                    let #name = match #built {
+                        ::core::result::Result::Ok(#name) => #name,
+                        ::core::result::Result::Err(err) => {
+                            #restore
+                            return ::core::result::Result::Err(#nested);
+                        }
+                    };
+                }
+            });
+            let value = fields.iter().map(|f| {
+                let name = &f.ident;
+                if f.opts.sub_builder {
+                    return quote_spanned!(f.field.span()=> #name);
+                }
+                let taken = match pattern {
+                    Pattern::Mutable => quote!(::core::mem::replace(&mut self.#name, ::core::option::Option::None)),
+                    Pattern::Owned => quote!(self.#name),
+                    Pattern::Immutable => quote!(::core::clone::Clone::clone(&self.#name)),
+                };
+                let taken = match &f.opts.env {
+                    Some(_) => {
+                        let env = env_local(f);
+                        quote!(#taken.or(#env))
+                    }
+                    None => taken,
+                };
+                let is_option = inner_for_option(&f.field.ty).is_some();
+                match default_value(f, struct_opts) {
+                    Some(default) if is_option => quote_spanned! {f.field.span()=>
+                        match #taken {
+                            ::core::option::Option::Some(#name) => ::core::option::Option::Some(#name),
+                            ::core::option::Option::None => #default,
+                        }
+                    },
+                    Some(default) => quote_spanned! {f.field.span()=>
+                        match #taken {
+                            ::core::option::Option::Some(#name) => #name,
+                            ::core::option::Option::None => #default,
+                        }
+                    },
+                    None if is_option => quote_spanned!(f.field.span()=> #taken),
+                    None => quote_spanned!(f.field.span()=> #taken.unwrap()),
+                }
+            });
+            let built = target.built(&value.collect::<Vec<_>>());
+            let built = match &struct_opts.build_fn.validate {
+                // The values were taken out of the builder, and are put back
+                // into the fields they came from, so that an error leaves the
+                // builder as it was.
+                Some(validate) if pattern == Pattern::Mutable => {
+                    let was_set = fields.iter().filter(|f| !f.opts.sub_builder).map(|f| {
+                        let name = &f.ident;
+                        let was_set = was_set_local(f);
+                        quote!(let #was_set = self.#name.is_some();)
+                    });
+                    let restore = fields.iter().map(|f| {
+                        let name = &field_ident(f);
+                        let was_set = was_set_local(f);
+                        if f.opts.sub_builder {
+                            return quote_spanned! {f.field.ty.span()=>
+                                self.#name.__restore(#name, &mut #was_set.iter());
+                            };
+                        }
+                        let value = if inner_for_option(&f.field.ty).is_some() {
+                            quote!(#name)
+                        } else {
+                            quote!(::core::option::Option::Some(#name))
+                        };
+                        quote! {
+                            if #was_set {
+                                self.#name = #value;
+                            }
+                        }
+                    });
+                    let bindings = fields.iter().map(|f| f.ident.to_token_stream());
+                    let bindings = bindings.collect::<Vec<_>>();
+                    let pattern = target.pattern(&bindings);
+                    quote! {
+                        #(#was_set)*
+                        let built = #built;
+                        if let ::core::result::Result::Err(err) = #validate(&built) {
+                            let err = #error_ty::Validation(#alloc::string::ToString::to_string(&err));
+                            #[allow(irrefutable_let_patterns, non_shorthand_field_patterns)]
+                            if let #pattern = built {
+                                #(#restore)*
+                            }
+                            return ::core::result::Result::Err(err);
+                        }
+                        ::core::result::Result::Ok(built)
+                    }
+                }
+                Some(validate) => quote! {
+                    let built = #built;
+                    #validate(&built).map_err(|err| {
+                        #error_ty::Validation(#alloc::string::ToString::to_string(&err))
+                    })?;
+                    ::core::result::Result::Ok(built)
+                },
+                None => quote!(::core::result::Result::Ok(#built)),
+            };
+            let struct_default = if struct_opts.default {
+                quote!(let __default: #ident #ty_generics = ::core::default::Default::default();)
+            } else {
+                quote!()
+            };
+            let set_twice = if target.tracks_set_twice() {
+                quote! {
+                    if let ::core::option::Option::Some(field) = self.__set_twice {
+                        return ::core::result::Result::Err(#error_ty::SetTwice(#alloc::string::ToString::to_string(field)));
+                    }
+                }
+            } else {
+                quote!()
+            };
+            let is_set = |f: &BuilderField| {
+                let name = &f.ident;
+                match &f.opts.env {
+                    Some(_) => {
+                        let env = env_local(f);
+                        quote!((self.#name.is_some() || #env.is_some()))
+                    }
+                    None => quote!(self.#name.is_some()),
+                }
+            };
+            let constraint =
+                fields.iter().flat_map(|f| {
+                    let field = f.ident.unraw().to_string();
+                    let set = is_set(f);
+                    let other = |other: &Ident| {
+                        let other = fields
+                            .iter()
+                            .find(|g| g.ident.unraw() == other.unraw())
+                            .unwrap();
+                        (other.ident.unraw().to_string(), is_set(other))
+                    };
+                    let requires = f.opts.requires.as_ref().map(other).map(
+                        |(required, required_set)| {
+                            quote_spanned! {f.field.span()=>
+                                if #set && !#required_set {
+                                    return ::core::result::Result::Err(#error_ty::Requires {
+                                        field: #alloc::string::ToString::to_string(#field),
+                                        required: #alloc::string::ToString::to_string(#required),
+                                    });
+                                }
+                            }
+                        },
+                    );
+                    let conflicts =
+                        f.opts
+                            .conflicts_with
+                            .as_ref()
+                            .map(other)
+                            .map(|(other, other_set)| {
+                                quote_spanned! {f.field.span()=>
+                                    if #set && #other_set {
+                                        return ::core::result::Result::Err(#error_ty::Conflicts {
+                                            field: #alloc::string::ToString::to_string(#field),
+                                            other: #alloc::string::ToString::to_string(#other),
+                                        });
+                                    }
+                                }
+                            });
+                    requires.into_iter().chain(conflicts)
+                });
+            quote! {
+                #set_twice
+                #(#env)*
+                let mut missing = #alloc::vec::Vec::new();
+                #(#check)*
+                if !missing.is_empty() {
+                    return ::core::result::Result::Err(#error_ty::MissingFields(missing));
+                }
+                #(#constraint)*
+                #(#sub_build)*
+                #struct_default
+
+                #built
+            }
+        };
+
+        let mut setters = vec![];
+        for f in fields {
+            let name = &field_ident(f);
+            for collection_setter in CollectionSetter::all(f) {
+                let CollectionSetter {
+                    name: setter_name,
+                    generics,
+                    args,
+                    where_clause,
+                    items,
+                } = collection_setter;
+                setters.push(setter(
+                    struct_opts,
+                    &f.vis,
+                    &setter_name,
+                    &generics,
+                    args,
+                    &where_clause,
+                    None,
+                    |this| {
+                        quote_spanned! {f.field.span()=>
+                            ::core::iter::Extend::extend(
+                                #this.#name.get_or_insert_with(::core::default::Default::default),
+                                #items,
+                            );
+                        }
+                    },
+                ));
+            }
+            let setter_name = match f.replace_setter() {
+                Some(setter_name) => setter_name,
+                None => continue,
+            };
+            let setter_vis = &f.vis;
+            if f.opts.sub_builder {
+                let sub_builder = sub_builder_ty(&f.field.ty).unwrap();
+                setters.push(quote_spanned! {f.field.ty.span()=>
+                    #setter_vis fn #setter_name(&mut self) -> &mut #sub_builder {
+                        &mut self.#name
+                    }
+                });
+                continue;
+            }
+            let (ty, _) = setter_ty(f);
+            let (arg_ty, value) = setter_arg(f, &ty, name);
+            let store = |this: &TokenStream| store_value(f, error_ty, &alloc, this);
+            let error = f.opts.validate.as_ref().map(|_| quote!(#error_ty));
+            setters.push(setter(
+                struct_opts,
+                setter_vis,
+                &setter_name,
+                &quote!(),
+                quote!(#name: #arg_ty),
+                &quote!(),
+                error.as_ref(),
+                |this| {
+                    let store = store(this);
+                    quote! {
+                        let #name: #ty = #value;
+                        #store
+                    }
+                },
+            ));
+            if let Some(try_setter) = f.try_setter() {
+                let try_into = quote!(::core::convert::TryInto<#ty>);
+                // A validator's error can only be returned as the builder's, so
+                // the conversion error goes there too.
+                let (bound, error, convert_err) = match error {
+                    Some(error) => (
+                        quote!(<__V as #try_into>::Error: ::core::fmt::Display),
+                        error,
+                        invalid_value(f, error_ty, &alloc, quote!(err)),
+                    ),
+                    None => (quote!(), quote!(<__V as #try_into>::Error), quote!(err)),
+                };
+                setters.push(setter(
+                    struct_opts,
+                    setter_vis,
+                    &try_setter,
+                    &quote!(<__V>),
+                    quote!(#name: __V),
+                    &quote!(where __V: #try_into, #bound),
+                    Some(&error),
+                    |this| {
+                        let store = store(this);
+                        quote! {
+                            let #name: #ty = match ::core::convert::TryInto::try_into(#name) {
+                                ::core::result::Result::Ok(#name) => #name,
+                                ::core::result::Result::Err(err) => return ::core::result::Result::Err(#convert_err),
+                            };
+                            #store
+                        }
+                    },
+                ));
+            }
+        }
+
+        // Pattern-independent, for the `merge` of the builders this one is a
+        // sub-builder of.
+        let merge_field = fields.iter().map(|f| {
+            let name = &field_ident(f);
+            if f.opts.sub_builder {
+                quote_spanned! {f.field.ty.span()=>
+                    self.#name.__merge(other.#name);
+                }
+            } else {
+                quote_spanned! {f.field.span()=>
+                    if other.#name.is_some() {
+                        self.#name = other.#name;
+                    }
+                }
+            }
+        });
+        let merge_set_twice = if target.tracks_set_twice() {
+            quote! {
+                if self.__set_twice.is_none() {
+                    self.__set_twice = other.__set_twice;
+                }
+            }
+        } else {
+            quote!()
+        };
+        setters.push(quote! {
+            #[doc(hidden)]
+            #vis fn __merge(&mut self, other: Self) {
+                #merge_set_twice
+                #(#merge_field)*
+            }
+        });
+        setters.push(setter(
+            struct_opts,
+            vis,
+            &Ident::new("merge", Span::call_site()),
+            &quote!(),
+            quote!(other: Self),
+            &quote!(),
+            None,
+            |this| quote!(#this.__merge(other);),
+        ));
+
+        // A skipped field can be neither set nor cleared.
+        for f in fields.iter().filter(|f| !f.opts.setter.skip) {
+            let name = &field_ident(f);
+            let setter_vis = &f.vis;
+            let unset = if f.opts.sub_builder {
+                quote_spanned!(f.field.ty.span()=> ::core::default::Default::default())
+            } else {
+                quote!(::core::option::Option::None)
+            };
+            if !f.opts.sub_builder {
+                let is_set = f.is_set_method();
+                setters.push(quote_spanned! {f.field.span()=>
+                    #setter_vis fn #is_set(&self) -> bool {
+                        self.#name.is_some()
+                    }
+                });
+            }
+            setters.push(setter(
+                struct_opts,
+                setter_vis,
+                &f.clear_method(),
+                &quote!(),
+                quote!(),
+                &quote!(),
+                None,
+                |this| quote_spanned!(f.field.ty.span()=> #this.#name = #unset;),
+            ));
+        }
+        setters.push(match struct_opts.pattern {
+            Pattern::Mutable => quote! {
+                #vis fn reset(&mut self) -> &mut Self {
+                    *self = ::core::default::Default::default();
+                    self
+                }
+            },
+            // The receiver stays, and the builder of a method has no default.
+            Pattern::Owned if target.receiver().is_some() => {
+                let init = builder_init(quote!(self.__receiver));
+                quote! {
+                    #vis fn reset(self) -> Self {
+                        #init
+                    }
+                }
+            }
+            Pattern::Owned => quote! {
+                #vis fn reset(self) -> Self {
+                    ::core::default::Default::default()
+                }
+            },
+            Pattern::Immutable => quote! {
+                #vis fn reset(&self) -> Self {
+                    ::core::default::Default::default()
+                }
+            },
+        });
+        // The fields `build()` reports as missing.
+        let missing = fields
+            .iter()
+            .filter(|f| is_required(f, struct_opts) || f.opts.sub_builder)
+            .map(|f| {
+                let name = &f.ident;
+                let unset = match &f.opts.env {
+                    Some(var) => quote!(self.#name.is_none() && ::std::env::var_os(#var).is_none()),
+                    None => quote!(self.#name.is_none()),
+                };
+                push_missing(f, unset)
+            });
+        setters.push(quote! {
+            /// The required fields which are neither set nor defaulted, in
+            /// declaration order, as `build()` reports them: those of a
+            /// sub-builder are named through its field, like `server.port`.
+            #vis fn missing_fields(&self) -> #alloc::vec::Vec<#alloc::string::String> {
+                let mut missing = #alloc::vec::Vec::new();
+                #(#missing)*
+                missing
+            }
+        });
+
+        let build_vis = struct_opts.build_fn.vis.as_ref().unwrap_or(vis);
+        let build_receiver = match struct_opts.pattern {
+            Pattern::Mutable => quote!(&mut self),
+            Pattern::Owned => quote!(self),
+            Pattern::Immutable => quote!(&self),
+        };
+
+        let qualifiers = target.call.as_ref().map(|call| &call.qualifiers);
+        let output = target.output(input);
+        // Pattern-independent, for the builders this one is a sub-builder of, and
+        // no more visible than `build()`.
+        let hidden_build = if has_builder(input, target) {
+            let body = build_fn_body(Pattern::Mutable);
+            // Which fields are set, those of sub-builders included, so that a
+            // mutable parent failing after `__build()` emptied this builder can
+            // give it back what it built as it was.
+            let was_set = fields.iter().map(|f| {
+                let name = &field_ident(f);
+                if f.opts.sub_builder {
+                    quote_spanned!(f.field.ty.span()=> was_set.append(&mut self.#name.__was_set());)
+                } else {
+                    quote!(was_set.push(self.#name.is_some());)
+                }
+            });
+            let restore = fields.iter().map(|f| {
+                let name = &field_ident(f);
+                if f.opts.sub_builder {
+                    return quote_spanned!(f.field.ty.span()=> self.#name.__restore(#name, was_set););
+                }
+                let value = if inner_for_option(&f.field.ty).is_some() {
+                    quote!(#name)
+                } else {
+                    quote!(::core::option::Option::Some(#name))
+                };
+                quote! {
+                    if let ::core::option::Option::Some(&true) = ::core::iter::Iterator::next(was_set) {
+                        self.#name = #value;
+                    }
+                }
+            });
+            let bindings = fields.iter().map(|f| f.ident.to_token_stream());
+            let pattern = target.pattern(&bindings.collect::<Vec<_>>());
+            quote! {
+                #[doc(hidden)]
+                #build_vis fn __build(&mut self) -> ::core::result::Result<#output, #error_ty> {
+                    #body
+                }
+
+                #[doc(hidden)]
+                #build_vis fn __was_set(&self) -> #alloc::vec::Vec<bool> {
+                    let mut was_set = #alloc::vec::Vec::new();
+                    #(#was_set)*
+                    was_set
+                }
+
+                #[doc(hidden)]
+                #build_vis fn __restore(&mut self, value: #output, was_set: &mut ::core::slice::Iter<'_, bool>) {
+                    #[allow(irrefutable_let_patterns, non_shorthand_field_patterns)]
+                    if let #pattern = value {
+                        #(#restore)*
+                    }
+                }
+            }
+        } else {
+            quote!()
+        };
+        let build_fn_body = match struct_opts.pattern {
+            Pattern::Mutable if has_builder(input, target) => quote!(self.__build()),
+            pattern => build_fn_body(pattern),
+        };
+        quote! {
+            #(#setters)*
+
+            #hidden_build
+
+            #build_vis #qualifiers fn #build_fn(#build_receiver) -> ::core::result::Result<#output, #error_ty> {
+                #build_fn_body
+            }
+        }
+    };
+
+    let builder_attrs = builder_attrs(struct_opts);
+
+    let cli = if struct_opts.cli {
+        cli::expand(input, struct_opts, target)
+    } else {
+        quote!()
+    };
+
+    let to_builder = to_builder(
+        input,
+        target,
+        quote!(#builder_ty #ty_generics),
+        |f, value| {
+            if f.opts.sub_builder {
+                quote_spanned!(f.field.ty.span()=> ::core::convert::From::from(#value))
+            } else if inner_for_option(&f.field.ty).is_some() {
+                value
+            } else {
+                quote!(::core::option::Option::Some(#value))
+            }
+        },
+    );
+
+    let fields = fields::expand(input, struct_opts, target);
+    let error = error::expand(struct_opts, vis, target);
+
+    let constructor = constructor(
+        input,
+        target,
+        quote!(#builder_ty #ty_generics),
+        builder_init(quote!(self)),
+    );
+    let default = match target.receiver() {
+        Some(_) => quote!(),
+        None => {
+            let new_builder = new_builder(input, target);
+            quote! {
+                impl #impl_generics ::core::default::Default for #builder_ty #ty_generics #where_clause {
+                    fn default() -> Self {
+                        #new_builder
+                    }
+                }
+            }
+        }
+    };
+    let has_builder = impl_has_builder(input, target);
+
+    quote! {
+        #constructor
+
+        #to_builder
+
+        #has_builder
+
+        #builder_attrs
+        #vis struct #builder_ty #generics #where_clause {
+            #builder_fields
+        }
+
+        #default
+
+        impl #impl_generics #builder_ty #ty_generics #where_clause {
+            #fields
+            #builder_impl
+            #cli
+        }
+
+        #error
+    }
+}
+
+/// The function returning a new `builder`, initialized by `body`: an
+/// associated function of the target, or a free function next to the one a
+/// builder calls. That of a method or associated function goes in an `impl`
+/// block of its type, and a method's takes the receiver, as `self`.
+fn constructor(
+    input: &DeriveInput,
+    target: &Target,
+    builder: TokenStream,
+    body: TokenStream,
+) -> TokenStream {
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let constructor = &target.constructor;
+    match &target.call {
+        Some(Call {
+            in_impl: Some(in_impl),
+            ..
+        }) => {
+            let header = &in_impl.header;
+            let (generics, _, where_clause) = in_impl.generics.split_for_impl();
+            let receiver = in_impl.receiver.as_ref().map(|ty| quote!(self: #ty));
+            quote! {
+                #header {
+                    #vis fn #constructor #generics(#receiver) -> #builder #where_clause {
+                        #body
+                    }
+                }
+            }
+        }
+        Some(_) => quote! {
+            #vis fn #constructor #impl_generics() -> #builder #where_clause {
+                #body
+            }
+        },
+        None => quote! {
+            impl #impl_generics #ident #ty_generics #where_clause {
+                #vis fn #constructor() -> #builder {
+                    #body
+                }
+            }
+        },
+    }
+}
+
+/// Expression calling the function made by `constructor`.
+fn new_builder(input: &DeriveInput, target: &Target) -> TokenStream {
+    let ident = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let constructor = &target.constructor;
+    match &target.call {
+        Some(Call {
+            in_impl: Some(in_impl),
+            ..
+        }) => {
+            let self_ty = &in_impl.self_ty;
+            let params = type_params(&in_impl.generics);
+            quote!(<#self_ty>::#constructor::<#(#params),*>())
+        }
+        Some(_) => {
+            let params = type_params(&input.generics);
+            quote!(#constructor::<#(#params),*>())
+        }
+        None => quote!(<#ident #ty_generics>::#constructor()),
+    }
+}
+
+/// The type and const parameters of `generics`, which are early-bound on a
+/// function unlike its lifetimes.
+fn type_params(generics: &syn::Generics) -> Vec<&Ident> {
+    generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(param) => Some(&param.ident),
+            syn::GenericParam::Const(param) => Some(&param.ident),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect()
+}
+
+/// The target is a struct, whose builder can be the `sub_builder` of another
+/// and be made from a value of it. An enum has a builder per variant, which a
+/// value may not be of, and a function returns no value to convert back.
+fn has_builder(input: &DeriveInput, target: &Target) -> bool {
+    matches!(input.data, Data::Struct(_)) && target.call.is_none()
+}
+
+/// `impl HasBuilder for #ident`, through which the builders this one is a
+/// `sub_builder` of name its types.
+fn impl_has_builder(input: &DeriveInput, target: &Target) -> TokenStream {
+    if !has_builder(input, target) {
+        return quote!();
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let Target {
+        builder_ty,
+        error_ty,
+        ..
+    } = target;
+    quote! {
+        impl #impl_generics ::derive_builder::__private::HasBuilder for #ident #ty_generics #where_clause {
+            type Builder = #builder_ty #ty_generics;
+            type Error = #error_ty;
+        }
+    }
+}
+
+/// `impl From<#ident> for #builder` and `#ident::to_builder()`, returning a
+/// builder with every field set from a value through `init(f, value)`. Only
+/// for structs, see `has_builder`.
+fn to_builder(
+    input: &DeriveInput,
+    target: &Target,
+    builder: TokenStream,
+    init: impl Fn(&BuilderField, TokenStream) -> TokenStream,
+) -> TokenStream {
+    if !has_builder(input, target) {
+        return quote!();
+    }
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let builder_ty = &target.builder_ty;
+    let field_init = |value: &dyn Fn(&syn::Member) -> TokenStream| {
+        let field = target.fields.iter().map(|f| {
+            let name = &field_ident(f);
+            let init = init(f, value(&f.member));
+            if f.opts.sub_builder {
+                return quote_spanned!(f.field.ty.span()=> #name: #init,);
+            }
+            quote_spanned!(f.field.span()=> #name: #init,)
+        });
+        let set_twice = set_twice_init(target);
+        quote! {
+            #builder_ty {
+                #(#field)*
+                #set_twice
+                __marker: ::core::marker::PhantomData,
+            }
+        }
+    };
+    let moved = field_init(&|member| quote!(value.#member));
+    let cloned = field_init(&|member| quote!(::core::clone::Clone::clone(&self.#member)));
+    // Bounds on concrete types must hold for the impl to compile, unless they
+    // are made higher-ranked.
+    let clone_bound = target.fields.iter().map(|f| {
+        let ty = &f.field.ty;
+        quote!(for<'__builder> #ty: ::core::clone::Clone)
+    });
+    quote! {
+        impl #impl_generics ::core::convert::From<#ident #ty_generics> for #builder #where_clause {
+            fn from(value: #ident #ty_generics) -> Self {
+                #moved
+            }
+        }
+
+        impl #impl_generics #ident #ty_generics #where_clause {
+            /// A builder with every field set to a copy of this value's.
+            #vis fn to_builder(&self) -> #builder
+            where
+                #(#clone_bound,)*
+            {
+                #cloned
+            }
+        }
+    }
+}
+
+/// Root of the paths to `String`, `Vec` and `format!` in generated code,
+/// which uses `core` for everything else.
+fn alloc(struct_opts: &StructOptions) -> TokenStream {
+    if struct_opts.no_std {
+        quote!(::alloc)
+    } else {
+        quote!(::std)
+    }
+}
+
+/// The derives and attributes of `builder(derive(...), struct_attrs(...))`,
+/// to put on the builder struct.
+fn builder_attrs(struct_opts: &StructOptions) -> TokenStream {
+    let mut derive = struct_opts
+        .derive
+        .iter()
+        .map(|path| quote!(#path))
+        .collect::<Vec<_>>();
+    // Immutable setters work on a copy of the builder.
+    let clone = struct_opts.derive.iter().any(|path| path_is(path, "Clone"));
+    if struct_opts.pattern == Pattern::Immutable && !clone {
+        derive.push(quote!(Clone));
+    }
+    // Every field of a runtime builder is optional already, and the builder
+    // starts out as its `Default`.
+    let serde = if struct_opts.serde {
+        derive.push(quote!(::serde::Deserialize));
+        quote!(#[serde(default)])
+    } else {
+        quote!()
+    };
+    let derive = if derive.is_empty() {
+        quote!()
+    } else {
+        quote!(#[derive(#(#derive),*)])
+    };
+    let attrs = &struct_opts.struct_attrs;
+    quote! {
+        #derive
+        #serde
+        #(#[#attrs])*
+    }
+}
+
+/// Whether `path` names `ident`, as in `Clone` or `::core::clone::Clone`.
+fn path_is(path: &syn::Path, ident: &str) -> bool {
+    path.segments
+        .last()
+        .is_some_and(|segment| segment.ident == ident)
+}
+
+/// Generates a setter `#name #generics(#args) #where_clause` for the
+/// builder's pattern, updating the builder through the expression `body` is
+/// given. With an `error` type, the setter returns a `Result` and `body` may
+/// return early with an error.
+#[allow(clippy::too_many_arguments)]
+fn setter(
+    struct_opts: &StructOptions,
+    vis: &syn::Visibility,
+    name: &Ident,
+    generics: &TokenStream,
+    args: TokenStream,
+    where_clause: &TokenStream,
+    error: Option<&TokenStream>,
+    body: impl FnOnce(&TokenStream) -> TokenStream,
+) -> TokenStream {
+    let (receiver, ret, this) = match struct_opts.pattern {
+        Pattern::Mutable => (quote!(&mut self), quote!(&mut Self), quote!(self)),
+        Pattern::Owned => (quote!(mut self), quote!(Self), quote!(self)),
+        Pattern::Immutable => (quote!(&self), quote!(Self), quote!(__builder)),
+    };
+    let clone = match struct_opts.pattern {
+        Pattern::Immutable => quote!(let mut __builder = ::core::clone::Clone::clone(self);),
+        _ => quote!(),
+    };
+    let body = body(&this);
+    let (ret, this) = match error {
+        Some(error) => (
+            quote!(::core::result::Result<#ret, #error>),
+            quote!(::core::result::Result::Ok(#this)),
+        ),
+        None => (ret, this),
+    };
+    quote! {
+        #vis fn #name #generics(#receiver, #args) -> #ret #where_clause {
+            #clone
+            #body
+            #this
+        }
+    }
+}
+
+/// Statements checking the local `#name` of the setter type of `f`, and
+/// storing it in the builder `this`. A value rejected by the field's validator
+/// is returned as an `InvalidValue` error.
+fn store_value(
+    f: &BuilderField,
+    error_ty: &Ident,
+    alloc: &TokenStream,
+    this: &TokenStream,
+) -> TokenStream {
+    let name = &f.ident;
+    let validate = f.opts.validate.as_ref().map(|validate| {
+        let err = invalid_value(f, error_ty, alloc, quote!(err));
+        quote_spanned! {validate.span()=>
+            if let ::core::result::Result::Err(err) = #validate(&#name) {
+                return ::core::result::Result::Err(#err);
+            }
+        }
+    });
+    let value = if setter_ty(f).1 {
+        quote!(::core::option::Option::Some(#name))
+    } else {
+        quote!(#name)
+    };
+    let set_twice = if f.opts.set_once {
+        let field = name.unraw().to_string();
+        quote! {
+            if #this.#name.is_some() && #this.__set_twice.is_none() {
+                #this.__set_twice = ::core::option::Option::Some(#field);
+            }
+        }
+    } else {
+        quote!()
+    };
+    quote_spanned! {f.field.span()=>
+        #validate
+        #set_twice
+        #this.#name = #value;
+    }
+}
+
+/// The `InvalidValue` error of `error_ty` for `f`, with `reason`, which is
+/// `Display`.
+fn invalid_value(
+    f: &BuilderField,
+    error_ty: &Ident,
+    alloc: &TokenStream,
+    reason: TokenStream,
+) -> TokenStream {
+    let field = f.ident.unraw().to_string();
+    quote! {
+        #error_ty::InvalidValue {
+            field: #alloc::string::ToString::to_string(#field),
+            reason: #alloc::string::ToString::to_string(&#reason),
+        }
+    }
+}
+
+/// The type taken by the setter of `f`, and whether it has to be wrapped in
+/// `Some` to be stored in the builder.
+fn setter_ty(f: &BuilderField) -> (Type, bool) {
+    match inner_for_option(&f.field.ty) {
+        Some(_) if !f.opts.setter.strip_option => (f.field.ty.clone(), false),
+        Some(inner) => (inner, true),
+        None => (f.field.ty.clone(), true),
+    }
+}
+
+/// The parameter type of a setter of `f` taking a `ty`, and the expression
+/// turning the parameter `arg` into a `ty`.
+fn setter_arg(f: &BuilderField, ty: &Type, arg: &Ident) -> (TokenStream, TokenStream) {
+    if f.opts.setter.into {
+        (
+            quote!(impl ::core::convert::Into<#ty>),
+            quote!(::core::convert::Into::into(#arg)),
+        )
+    } else {
+        (quote!(#ty), quote!(#arg))
+    }
+}
+
+/// A setter adding to a collection field through its `Extend` impl: the
+/// `each` setter taking a single element, or the `extend_` one taking many.
+pub(crate) struct CollectionSetter {
+    pub name: Ident,
+    pub generics: TokenStream,
+    pub args: TokenStream,
+    pub where_clause: TokenStream,
+    /// The elements added, as an `IntoIterator` for the collection's `Extend`.
+    pub items: TokenStream,
+}
+
+impl CollectionSetter {
+    /// Both setters of a field with `#[builder(each = "...")]`, or the
+    /// `extend_` one of other collections.
+    pub fn all(f: &BuilderField) -> Vec<Self> {
+        let each = f
+            .opts
+            .each
+            .iter()
+            .map(|each| CollectionSetter::each(f, each));
+        let extend = f.extend_setter().map(|_| CollectionSetter::extend(f));
+        each.chain(extend).collect()
+    }
+
+    fn each(f: &BuilderField, each: &Ident) -> Self {
+        let ty = &f.field.ty;
+        let (generics, args, where_clause, items) = match collection_for(ty).unwrap() {
+            Collection::Items(item) => {
+                let (arg_ty, value) = setter_arg(f, item, each);
+                (
+                    quote!(),
+                    quote!(#each: #arg_ty),
+                    quote!(),
+                    quote!(::core::option::Option::Some(#value)),
+                )
+            }
+            Collection::Map(key_ty, value_ty) => {
+                let key = Ident::new("key", each.span());
+                let value = Ident::new("value", each.span());
+                let (key_ty, key) = setter_arg(f, key_ty, &key);
+                let (value_ty, value) = setter_arg(f, value_ty, &value);
+                (
+                    quote!(),
+                    quote!(key: #key_ty, value: #value_ty),
+                    quote!(),
+                    quote!(::core::option::Option::Some((#key, #value))),
+                )
+            }
+            // Anything the collection can be extended with; there is no element
+            // type to convert `into`.
+            Collection::Extend => (
+                quote!(<__Item>),
+                quote!(#each: __Item),
+                quote!(where #ty: ::core::iter::Extend<__Item>),
+                quote!(::core::option::Option::Some(#each)),
+            ),
+        };
+        CollectionSetter {
+            name: each.clone(),
+            generics,
+            args,
+            where_clause,
+            items,
+        }
+    }
+
+    fn extend(f: &BuilderField) -> Self {
+        let ty = &f.field.ty;
+        let name = &f.ident;
+        let into = f.opts.setter.into;
+        let (generics, args, where_clause, items) = match collection_for(ty).unwrap() {
+            Collection::Items(item) if into => (
+                quote!(),
+                quote!(#name: impl ::core::iter::IntoIterator<Item = impl ::core::convert::Into<#item>>),
+                quote!(),
+                quote!(::core::iter::Iterator::map(::core::iter::IntoIterator::into_iter(#name), ::core::convert::Into::into)),
+            ),
+            Collection::Items(item) => (
+                quote!(),
+                quote!(#name: impl ::core::iter::IntoIterator<Item = #item>),
+                quote!(),
+                quote!(#name),
+            ),
+            Collection::Map(key, value) if into => (
+                quote!(),
+                quote!(#name: impl ::core::iter::IntoIterator<Item = (impl ::core::convert::Into<#key>, impl ::core::convert::Into<#value>)>),
+                quote!(),
+                quote! {
+                    ::core::iter::Iterator::map(::core::iter::IntoIterator::into_iter(#name), |(key, value)| {
+                        (::core::convert::Into::into(key), ::core::convert::Into::into(value))
+                    })
+                },
+            ),
+            Collection::Map(key, value) => (
+                quote!(),
+                quote!(#name: impl ::core::iter::IntoIterator<Item = (#key, #value)>),
+                quote!(),
+                quote!(#name),
+            ),
+            Collection::Extend => (
+                quote!(<__Items: ::core::iter::IntoIterator>),
+                quote!(#name: __Items),
+                quote!(where #ty: ::core::iter::Extend<__Items::Item>),
+                quote!(#name),
+            ),
+        };
+        CollectionSetter {
+            name: f.extend_setter().unwrap(),
+            generics,
+            args,
+            where_clause,
+            items,
+        }
+    }
+}
+
+/// How the `each` setter of a collection field adds to it.
+pub(crate) enum Collection<'a> {
+    /// `Vec<T>`, `VecDeque<T>`, `HashSet<T>`...: the setter takes a `T`.
+    Items(&'a Type),
+    /// `HashMap<K, V>` and `BTreeMap<K, V>`: the setter takes a key and a value.
+    Map(&'a Type, &'a Type),
+    /// Any other `Default + Extend<A>` type: the setter takes any `A`.
+    Extend,
+}
+
+/// The kind of collection `ty` is, or `None` if it cannot be one.
+pub(crate) fn collection_for(ty: &Type) -> Option<Collection<'_>> {
+    let segment = match ty {
+        Type::Path(syn::TypePath { qself: None, path }) => path.segments.last()?,
+        _ => return None,
+    };
+    let args = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(generic) => generic
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let container = segment.ident.to_string();
+    match (container.as_str(), args.as_slice()) {
+        ("Option", _) => None,
+        ("Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" | "BinaryHeap", [item, ..]) => {
+            Some(Collection::Items(item))
+        }
+        ("HashMap" | "BTreeMap", [key, value, ..]) => Some(Collection::Map(key, value)),
+        _ => Some(Collection::Extend),
+    }
+}
+
+/// `ty` is a collection of the standard library, which gets an `extend_`
+/// setter even without `each`.
+pub(crate) fn is_std_collection(ty: &Type) -> bool {
+    matches!(
+        collection_for(ty),
+        Some(Collection::Items(_) | Collection::Map(..))
+    )
+}
+
+/// Expression to build a field left unset on the builder with, if any.
+fn default_value(f: &BuilderField, struct_opts: &StructOptions) -> Option<TokenStream> {
+    match &f.opts.default {
+        Some(FieldDefault::Trait) => Some(quote!(::core::default::Default::default())),
+        Some(FieldDefault::Expr(expr)) => Some(quote!(#expr)),
+        None if struct_opts.default => {
+            let member = &f.member;
+            Some(quote!(__default.#member))
+        }
+        None if f.opts.each.is_some() || cli::is_switch(f, struct_opts) => {
+            Some(quote!(::core::default::Default::default()))
+        }
+        None => None,
+    }
+}
+
+/// Local variable of `build()` holding the value read from the environment
+/// for a field with `#[builder(env = "...")]`.
+fn env_local(f: &BuilderField) -> Ident {
+    format_ident!("__env_{}", f.ident.unraw())
+}
+
+/// The name of the field in the builder. That of a sub-builder is spanned at
+/// its type, like the code using it, so that a type without a builder is
+/// reported there once.
+fn field_ident(f: &BuilderField) -> Ident {
+    let mut name = f.ident.clone();
+    if f.opts.sub_builder {
+        name.set_span(f.field.ty.span());
+    }
+    name
+}
+
+/// Local variable of `build()` recording whether the field was set before its
+/// value was taken out of the builder.
+fn was_set_local(f: &BuilderField) -> Ident {
+    format_ident!("__was_set_{}", f.ident.unraw())
+}
+
+/// Initializes the `__set_twice` field of builders which have one.
+fn set_twice_init(target: &Target) -> TokenStream {
+    if target.tracks_set_twice() {
+        quote!(__set_twice: ::core::option::Option::None,)
+    } else {
+        quote!()
+    }
+}
+
+/// Whether `build()` has to fail when the field is left unset.
+fn is_required(f: &BuilderField, struct_opts: &StructOptions) -> bool {
+    f.opts.each.is_none()
+        && !f.opts.sub_builder
+        && f.opts.default.is_none()
+        && !struct_opts.default
+        && inner_for_option(&f.field.ty).is_none()
+        && !cli::is_switch(f, struct_opts)
+}
+
+/// A type mentioning every lifetime and type parameter of `generics`, for a
+/// `PhantomData` keeping all of them in use: the builder of an enum variant
+/// may store only some of them, and a typestate builder none until its
+/// required fields are set.
+fn phantom_marker(generics: &syn::Generics) -> TokenStream {
+    let params = generics.params.iter().filter_map(|param| match param {
+        syn::GenericParam::Type(param) => {
+            let ident = &param.ident;
+            Some(quote!(fn() -> #ident))
+        }
+        syn::GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            Some(quote!(&#lifetime ()))
+        }
+        syn::GenericParam::Const(_) => None,
+    });
+    quote!((#(#params,)*))
+}
+
+fn inner_for(container: &str, ty: &Type) -> Option<Type> {
+    match ty {
+        Type::Path(syn::TypePath {
+            path: syn::Path { segments, .. },
+            ..
+        }) if segments[0].ident == container => {
+            let segment = &segments[0];
+
+            match &segment.arguments {
+                syn::PathArguments::AngleBracketed(generic) => {
+                    match generic.args.first().unwrap() {
+                        syn::GenericArgument::Type(ty) => Some(ty.clone()),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            }
+        }
+
+        _ => None,
+    }
+}
+
+/// `<a::Server<T> as HasBuilder>::Builder` for a field of type `a::Server<T>`,
+/// the builder derived for the field's type. `None` for types which cannot
+/// derive one.
+fn sub_builder_ty(ty: &Type) -> Option<TokenStream> {
+    match ty {
+        Type::Path(syn::TypePath { qself: None, .. }) if inner_for_option(ty).is_none() => {
+            Some(quote_spanned! {ty.span()=>
+                <#ty as ::derive_builder::__private::HasBuilder>::Builder
+            })
+        }
+        _ => None,
+    }
+}
+
+fn inner_for_option(ty: &Type) -> Option<Type> {
+    inner_for("Option", ty)
+}
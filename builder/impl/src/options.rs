@@ -0,0 +1,670 @@
+use crate::{
+    collection_for, inner_for_option, is_std_collection, path_is, sub_builder_ty, Collection,
+};
+use proc_macro2::Ident;
+use syn::{Attribute, Expr, Field, Lit, LitStr, Meta, MetaList, NestedMeta, Path, Visibility};
+
+/// Options set on the struct itself through `#[builder(...)]`.
+#[derive(Default)]
+pub(crate) struct StructOptions {
+    /// Encode the set/unset state of every required field in the builder's
+    /// type, so that `build()` only exists once all of them have been set.
+    pub typestate: bool,
+    /// Take the value of every field left unset from the struct's own
+    /// `Default` impl.
+    pub default: bool,
+    /// How the setters and `build()` receive the builder.
+    pub pattern: Pattern,
+    /// Name of the builder type, instead of `CommandBuilder`.
+    pub name: Option<Ident>,
+    /// Name of the function returning a new builder, instead of `builder`.
+    pub constructor: Option<Ident>,
+    /// Defaults for the setter options of every field.
+    pub setter: SetterOptions,
+    pub build_fn: BuildFnOptions,
+    /// Traits to derive on the builder struct.
+    pub derive: Vec<Path>,
+    /// Attributes to put on the builder struct.
+    pub struct_attrs: Vec<Meta>,
+    /// Generate `from_args`, filling the builder from command-line flags.
+    pub cli: bool,
+    /// Generated code only uses `core` and `alloc`.
+    pub no_std: bool,
+    /// Derive `serde::Deserialize` on the builder, with every field optional.
+    pub serde: bool,
+}
+
+/// Options of the generated `build()`, set through
+/// `#[builder(build_fn(...))]`.
+#[derive(Default)]
+pub(crate) struct BuildFnOptions {
+    /// Function checking the built value before `build()` returns it.
+    pub validate: Option<Path>,
+    /// Name of the function, instead of `build`.
+    pub name: Option<Ident>,
+    /// Visibility of the function, instead of the struct's.
+    pub vis: Option<Visibility>,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+pub(crate) enum Pattern {
+    /// `&mut self -> &mut Self` setters, `build(&mut self)` takes the values out.
+    #[default]
+    Mutable,
+    /// `self -> Self` setters, `build(self)` consumes the builder.
+    Owned,
+    /// `&self -> Self` setters working on a clone, `build(&self)` clones the
+    /// values.
+    Immutable,
+}
+
+/// Options set through `#[builder(setter(...))]`, on a field or as defaults
+/// on the struct.
+#[derive(Clone)]
+pub(crate) struct SetterOptions {
+    /// Setters accept anything converting `Into` the field's type.
+    pub into: bool,
+    /// Setters of `Option<T>` fields accept a `T`.
+    pub strip_option: bool,
+    /// The field gets no setter and always takes its default value.
+    pub skip: bool,
+    /// Name of the setter, instead of the field's.
+    pub name: Option<Ident>,
+    /// Prepended to the field's name to name its setter: `with_` for
+    /// `with_port`.
+    pub prefix: Option<String>,
+}
+
+/// Options set on a single field through `#[builder(...)]`.
+pub(crate) struct FieldOptions {
+    /// Name of the setter adding a single element to a collection field.
+    pub each: Option<Ident>,
+    /// Value to use when the field is left unset.
+    pub default: Option<FieldDefault>,
+    /// Name of the setter of a tuple field, instead of its position.
+    pub name: Option<Ident>,
+    /// Store the builder of the field's type, built along with this one.
+    pub sub_builder: bool,
+    /// Also generate a `try_` setter, taking anything converting `TryInto` the
+    /// field's type.
+    pub try_setter: bool,
+    /// Function checking every value given to the setters of the field.
+    pub validate: Option<Path>,
+    /// Environment variable parsed for the field when it is left unset.
+    pub env: Option<LitStr>,
+    /// Fail to build if the setters of the field are called more than once.
+    pub set_once: bool,
+    /// Field which has to be set whenever this one is.
+    pub requires: Option<Ident>,
+    /// Field which cannot be set along with this one.
+    pub conflicts_with: Option<Ident>,
+    pub setter: SetterOptions,
+    /// Visibility of the setters, instead of the struct's.
+    pub vis: Option<Visibility>,
+}
+
+pub(crate) enum FieldDefault {
+    /// `#[builder(default)]`
+    Trait,
+    /// `#[builder(default = "...")]`
+    Expr(Box<Expr>),
+}
+
+/// Options accepted on the struct, and their expected form.
+const STRUCT_OPTIONS: &[(&str, &str)] = &[
+    ("typestate", "typestate"),
+    ("default", "default"),
+    ("pattern", r#"pattern = "...""#),
+    ("name", r#"name = "...""#),
+    ("constructor", r#"constructor = "...""#),
+    ("setter", "setter(...)"),
+    ("build_fn", "build_fn(...)"),
+    ("derive", "derive(...)"),
+    ("struct_attrs", "struct_attrs(...)"),
+    ("cli", "cli"),
+    ("no_std", "no_std"),
+    ("serde", "serde"),
+];
+
+impl StructOptions {
+    pub fn from_attrs(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut res = StructOptions::default();
+        let mut seen = Seen::default();
+        let mut pattern_lit = None;
+        let mut cli_path = None;
+        let mut serde_path = None;
+        for nested in builder_metas(attrs)? {
+            let meta = match &nested {
+                NestedMeta::Meta(meta) => meta,
+                NestedMeta::Lit(lit) => {
+                    return Err(syn::Error::new_spanned(lit, "expected a builder option"))
+                }
+            };
+            let key = option_key(meta);
+            let form = match STRUCT_OPTIONS.iter().find(|(option, _)| *option == key) {
+                Some((_, form)) => form,
+                None => {
+                    let expected = STRUCT_OPTIONS
+                        .iter()
+                        .map(|(option, _)| format!("`{}`", option))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    return Err(syn::Error::new_spanned(
+                        meta.path(),
+                        format!("unknown builder option, expected one of {}", expected),
+                    ));
+                }
+            };
+            let err = || syn::Error::new_spanned(meta, format!("expected `builder({})`", form));
+            match (key.as_str(), meta) {
+                ("typestate", Meta::Path(_)) => res.typestate = true,
+                ("no_std", Meta::Path(_)) => res.no_std = true,
+                ("cli", Meta::Path(path)) => {
+                    cli_path = Some(path.clone());
+                    res.cli = true;
+                }
+                ("serde", Meta::Path(path)) => {
+                    if !cfg!(feature = "serde") {
+                        return Err(syn::Error::new_spanned(
+                            path,
+                            "`serde` requires the `serde` feature of `derive_builder`",
+                        ));
+                    }
+                    serde_path = Some(path.clone());
+                    res.serde = true;
+                }
+                ("default", Meta::Path(_)) => res.default = true,
+                ("pattern", Meta::NameValue(nv)) => {
+                    pattern_lit = Some(nv.lit.clone());
+                    res.pattern = match lit_str(&nv.lit)?.value().as_str() {
+                        "mutable" => Pattern::Mutable,
+                        "owned" => Pattern::Owned,
+                        "immutable" => Pattern::Immutable,
+                        _ => {
+                            return Err(syn::Error::new_spanned(
+                                &nv.lit,
+                                r#"expected `"owned"`, `"mutable"` or `"immutable"`"#,
+                            ))
+                        }
+                    };
+                }
+                ("name", Meta::NameValue(nv)) => res.name = Some(lit_ident(&nv.lit)?),
+                ("constructor", Meta::NameValue(nv)) => {
+                    res.constructor = Some(lit_ident(&nv.lit)?);
+                }
+                ("setter", Meta::List(list)) => res.setter.parse(list, false)?,
+                ("build_fn", Meta::List(list)) => res.build_fn.parse(list)?,
+                ("derive", Meta::List(list)) => {
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(Meta::Path(path)) if path_is(path, "Default") => {
+                                return Err(syn::Error::new_spanned(
+                                    path,
+                                    "builders always implement `Default`",
+                                ))
+                            }
+                            NestedMeta::Meta(Meta::Path(path)) => res.derive.push(path.clone()),
+                            _ => {
+                                return Err(syn::Error::new_spanned(
+                                    nested,
+                                    "expected the path of a derive macro",
+                                ))
+                            }
+                        }
+                    }
+                }
+                ("struct_attrs", Meta::List(list)) => {
+                    for nested in &list.nested {
+                        match nested {
+                            NestedMeta::Meta(meta) => res.struct_attrs.push(meta.clone()),
+                            NestedMeta::Lit(lit) => {
+                                return Err(syn::Error::new_spanned(lit, "expected an attribute"))
+                            }
+                        }
+                    }
+                }
+                _ => return Err(err()),
+            }
+            seen.check(meta.path())?;
+        }
+        match pattern_lit {
+            Some(lit) if res.typestate && res.pattern != Pattern::Owned => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "typestate builders always use the owned pattern",
+                ))
+            }
+            _ => {}
+        }
+        match cli_path {
+            Some(path) if res.typestate => return Err(syn::Error::new_spanned(
+                path,
+                "`cli` fills the builder one flag at a time, which typestate builders cannot do",
+            )),
+            _ => {}
+        }
+        match serde_path {
+            Some(path) if res.typestate => {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    "the type of a typestate builder changes as it is set, so it cannot be deserialized",
+                ))
+            }
+            _ => {}
+        }
+        match &res.build_fn.validate {
+            Some(validate) if res.typestate => Err(syn::Error::new_spanned(
+                validate,
+                "typestate builders cannot fail to build, so they cannot be validated",
+            )),
+            _ => Ok(res),
+        }
+    }
+}
+
+impl Default for SetterOptions {
+    fn default() -> Self {
+        SetterOptions {
+            into: false,
+            strip_option: true,
+            skip: false,
+            name: None,
+            prefix: None,
+        }
+    }
+}
+
+impl SetterOptions {
+    /// Applies the items of `setter(...)` on top of the current options.
+    /// `skip` and `name` only make sense for a single field.
+    fn parse(&mut self, list: &MetaList, on_field: bool) -> syn::Result<()> {
+        let mut seen = Seen::default();
+        for nested in &list.nested {
+            let err = || {
+                let expected = if on_field {
+                    r#"expected `setter(into)`, `setter(strip_option)`, `setter(skip)`, `setter(name = "...")` or `setter(prefix = "...")`"#
+                } else {
+                    r#"expected `setter(into)`, `setter(strip_option)` or `setter(prefix = "...")`"#
+                };
+                syn::Error::new_spanned(nested, expected)
+            };
+            let meta = match nested {
+                NestedMeta::Meta(meta) => meta,
+                NestedMeta::Lit(_) => return Err(err()),
+            };
+            match (option_key(meta).as_str(), meta) {
+                ("name", Meta::NameValue(nv)) if on_field => self.name = Some(lit_ident(&nv.lit)?),
+                ("prefix", Meta::NameValue(nv)) => {
+                    let prefix = lit_str(&nv.lit)?;
+                    if syn::parse_str::<Ident>(&format!("{}x", prefix.value())).is_err() {
+                        return Err(syn::Error::new_spanned(
+                            prefix,
+                            "expected the start of an identifier",
+                        ));
+                    }
+                    self.prefix = Some(prefix.value());
+                }
+                (key, _) => {
+                    let flag = match key {
+                        "into" => &mut self.into,
+                        "strip_option" => &mut self.strip_option,
+                        "skip" if on_field => &mut self.skip,
+                        _ => return Err(err()),
+                    };
+                    *flag = match meta {
+                        Meta::Path(_) => true,
+                        Meta::NameValue(nv) => match &nv.lit {
+                            Lit::Bool(value) => value.value,
+                            lit => {
+                                return Err(syn::Error::new_spanned(
+                                    lit,
+                                    "expected `true` or `false`",
+                                ))
+                            }
+                        },
+                        Meta::List(_) => return Err(err()),
+                    };
+                }
+            }
+            seen.check(meta.path())?;
+        }
+        Ok(())
+    }
+}
+
+impl BuildFnOptions {
+    fn parse(&mut self, list: &MetaList) -> syn::Result<()> {
+        let mut seen = Seen::default();
+        for nested in &list.nested {
+            let err = || {
+                syn::Error::new_spanned(
+                    nested,
+                    r#"expected `build_fn(validate = "...")`, `build_fn(name = "...")` or `build_fn(vis = "...")`"#,
+                )
+            };
+            let nv = match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) => nv,
+                _ => return Err(err()),
+            };
+            if nv.path.is_ident("validate") {
+                self.validate = Some(lit_str(&nv.lit)?.parse()?);
+            } else if nv.path.is_ident("name") {
+                self.name = Some(lit_ident(&nv.lit)?);
+            } else if nv.path.is_ident("vis") {
+                self.vis = Some(lit_str(&nv.lit)?.parse()?);
+            } else {
+                return Err(err());
+            }
+            seen.check(&nv.path)?;
+        }
+        Ok(())
+    }
+}
+
+impl FieldOptions {
+    /// Parses the options of `field`, starting from the defaults set on the
+    /// struct.
+    pub fn from_field(field: &Field, struct_opts: &StructOptions) -> syn::Result<Self> {
+        let mut res = FieldOptions {
+            each: None,
+            default: None,
+            name: None,
+            sub_builder: false,
+            try_setter: false,
+            validate: None,
+            env: None,
+            set_once: false,
+            requires: None,
+            conflicts_with: None,
+            setter: struct_opts.setter.clone(),
+            vis: None,
+        };
+        let mut seen = Seen::default();
+        let mut skip = None;
+        let mut try_setter = None;
+        let mut set_once = None;
+        for attr in field
+            .attrs
+            .iter()
+            .filter(|attr| attr.path.is_ident("builder"))
+        {
+            let meta = attr.parse_meta()?;
+            let err = || syn::Error::new_spanned(&meta, r#"expected `builder(each = "...")`"#);
+            let nested = match &meta {
+                Meta::List(list) => &list.nested,
+                _ => return Err(err()),
+            };
+            for nested in nested {
+                let meta = match nested {
+                    NestedMeta::Meta(meta) => meta,
+                    NestedMeta::Lit(_) => return Err(err()),
+                };
+                match (option_key(meta).as_str(), meta) {
+                    ("each", Meta::NameValue(nv)) => {
+                        if collection_for(&field.ty).is_none() {
+                            return Err(syn::Error::new_spanned(
+                                &field.ty,
+                                "`each` requires a collection field implementing `Default` and `Extend`",
+                            ));
+                        }
+                        res.each = Some(lit_ident(&nv.lit)?);
+                    }
+                    ("default", Meta::Path(_)) => res.default = Some(FieldDefault::Trait),
+                    ("default", Meta::NameValue(nv)) => {
+                        let expr = lit_str(&nv.lit)?.parse()?;
+                        res.default = Some(FieldDefault::Expr(Box::new(expr)));
+                    }
+                    ("name", Meta::NameValue(nv)) => {
+                        if field.ident.is_some() {
+                            return Err(syn::Error::new_spanned(
+                                nv,
+                                "`name` is only needed on the fields of tuple structs and variants",
+                            ));
+                        }
+                        res.name = Some(lit_ident(&nv.lit)?);
+                    }
+                    ("try_setter", Meta::Path(path)) => {
+                        if struct_opts.typestate {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "typestate builders only have infallible setters",
+                            ));
+                        }
+                        try_setter = Some(path.clone());
+                        res.try_setter = true;
+                    }
+                    ("validate", Meta::NameValue(nv)) => {
+                        if struct_opts.typestate {
+                            return Err(syn::Error::new_spanned(
+                                nv,
+                                "typestate builders have no error type, so they cannot validate fields",
+                            ));
+                        }
+                        res.validate = Some(lit_str(&nv.lit)?.parse()?);
+                    }
+                    ("set_once", Meta::Path(path)) => {
+                        if struct_opts.typestate {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "typestate builders have a setter per field which can only be called once",
+                            ));
+                        }
+                        set_once = Some(path.clone());
+                        res.set_once = true;
+                    }
+                    ("requires" | "conflicts_with", Meta::NameValue(nv)) => {
+                        if struct_opts.typestate {
+                            return Err(syn::Error::new_spanned(
+                                nv,
+                                "typestate builders cannot fail to build, so they cannot check which fields are set",
+                            ));
+                        }
+                        let other = Some(lit_ident(&nv.lit)?);
+                        if nv.path.is_ident("requires") {
+                            res.requires = other;
+                        } else {
+                            res.conflicts_with = other;
+                        }
+                    }
+                    ("sub_builder", Meta::Path(path)) => {
+                        if struct_opts.typestate {
+                            return Err(syn::Error::new_spanned(
+                                path,
+                                "typestate builders cannot fail to build, so they cannot have sub-builders",
+                            ));
+                        }
+                        if sub_builder_ty(&field.ty).is_none() {
+                            return Err(syn::Error::new_spanned(
+                                &field.ty,
+                                "`sub_builder` requires a field whose type derives `Builder`",
+                            ));
+                        }
+                        res.sub_builder = true;
+                    }
+                    ("env", Meta::NameValue(nv)) => {
+                        if struct_opts.no_std {
+                            return Err(syn::Error::new_spanned(
+                                nv,
+                                "`env` reads the environment through `std`, which `no_std` builders cannot use",
+                            ));
+                        }
+                        if struct_opts.typestate {
+                            return Err(syn::Error::new_spanned(
+                                nv,
+                                "typestate builders cannot fail to build, so they cannot read the environment",
+                            ));
+                        }
+                        res.env = Some(lit_str(&nv.lit)?.clone());
+                    }
+                    ("vis", Meta::NameValue(nv)) => {
+                        res.vis = Some(lit_str(&nv.lit)?.parse()?);
+                    }
+                    ("setter", Meta::List(list)) => {
+                        res.setter.parse(list, true)?;
+                        let item = |key| {
+                            list.nested.iter().find(|nested| match nested {
+                                NestedMeta::Meta(meta) => meta.path().is_ident(key),
+                                NestedMeta::Lit(_) => false,
+                            })
+                        };
+                        if let Some(item) = item("skip") {
+                            skip = Some(item.clone());
+                        }
+                        match item("strip_option") {
+                            Some(strip_option) if inner_for_option(&field.ty).is_none() => {
+                                return Err(syn::Error::new_spanned(
+                                    strip_option,
+                                    "`strip_option` requires a field of type `Option<T>`",
+                                ))
+                            }
+                            _ => {}
+                        }
+                    }
+                    _ => return Err(err()),
+                }
+                seen.check(meta.path())?;
+            }
+        }
+        let setter_options = try_setter
+            .iter()
+            .map(|path| ("try_setter", path))
+            .chain(res.validate.iter().map(|path| ("validate", path)))
+            .chain(set_once.iter().map(|path| ("set_once", path)));
+        for (option, path) in setter_options {
+            if res.each.is_some()
+                || is_std_collection(&field.ty)
+                || res.sub_builder
+                || res.setter.skip
+            {
+                return Err(syn::Error::new_spanned(
+                    path,
+                    format!(
+                        "`{}` applies to the setter of a single value, not to collections, sub-builders or skipped fields",
+                        option,
+                    ),
+                ));
+            }
+        }
+        if res.sub_builder && (res.each.is_some() || res.default.is_some()) {
+            return Err(syn::Error::new_spanned(
+                &field.ty,
+                "a `sub_builder` field is built by its own builder, without `each` or `default`",
+            ));
+        }
+        let constraint = res.requires.iter().chain(&res.conflicts_with).next();
+        match constraint {
+            Some(other) if res.sub_builder => {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "a `sub_builder` field is always set, so it cannot require or conflict with another field",
+                ))
+            }
+            _ => {}
+        }
+        match skip {
+            Some(skip) if res.setter.skip && (res.each.is_some() || res.sub_builder) => {
+                return Err(syn::Error::new_spanned(
+                    skip,
+                    "a skipped field has no setters, so it cannot have `each` or `sub_builder`",
+                ))
+            }
+            Some(skip)
+                if res.setter.skip
+                    && res.default.is_none()
+                    && !struct_opts.default
+                    && inner_for_option(&field.ty).is_none() =>
+            {
+                return Err(syn::Error::new_spanned(
+                    skip,
+                    "a skipped field is never set, so it requires `#[builder(default)]`",
+                ))
+            }
+            _ => {}
+        }
+        match &res.env {
+            Some(env) if res.each.is_some() || res.sub_builder => {
+                return Err(syn::Error::new_spanned(
+                    env,
+                    "`env` sets a single value, it cannot fill a collection or sub-builder",
+                ))
+            }
+            _ => {}
+        }
+        if struct_opts.cli {
+            res.check_cli(field)?;
+        }
+        Ok(res)
+    }
+
+    /// Rejects the fields `from_args` has no flag for.
+    fn check_cli(&self, field: &Field) -> syn::Result<()> {
+        let unsupported = if field.ident.is_none() {
+            "`cli` names its flags after the fields, so they need names"
+        } else if self.sub_builder {
+            "`cli` cannot fill a `sub_builder` field"
+        } else if let (Some(_), Some(Collection::Extend)) = (&self.each, collection_for(&field.ty))
+        {
+            "`cli` cannot parse the elements of this collection, only those of std collections"
+        } else if self.each.is_none() && !self.setter.skip && is_std_collection(&field.ty) {
+            "`cli` fills a collection from a repeated flag, which requires `each`"
+        } else {
+            return Ok(());
+        };
+        Err(syn::Error::new_spanned(field, unsupported))
+    }
+}
+
+/// Collects the items listed in every `#[builder(...)]` attribute.
+fn builder_metas(attrs: &[Attribute]) -> syn::Result<Vec<NestedMeta>> {
+    let mut res = vec![];
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("builder")) {
+        match attr.parse_meta()? {
+            Meta::List(list) => res.extend(list.nested),
+            meta => return Err(syn::Error::new_spanned(meta, "expected `builder(...)`")),
+        }
+    }
+    Ok(res)
+}
+
+/// Name of the option set by `meta`, `each` for `each = "..."`.
+fn option_key(meta: &Meta) -> String {
+    meta.path()
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default()
+}
+
+fn lit_str(lit: &Lit) -> syn::Result<&LitStr> {
+    match lit {
+        Lit::Str(lit) => Ok(lit),
+        _ => Err(syn::Error::new_spanned(lit, "expected a string literal")),
+    }
+}
+
+/// The identifier spelled by the string literal `lit`.
+fn lit_ident(lit: &Lit) -> syn::Result<Ident> {
+    lit_str(lit)?
+        .parse()
+        .map_err(|_| syn::Error::new_spanned(lit, "expected an identifier"))
+}
+
+/// Options set so far within one attribute list, to reject those set twice.
+#[derive(Default)]
+struct Seen(Vec<String>);
+
+impl Seen {
+    fn check(&mut self, path: &Path) -> syn::Result<()> {
+        let key = path
+            .get_ident()
+            .map(ToString::to_string)
+            .unwrap_or_default();
+        if self.0.contains(&key) {
+            return Err(syn::Error::new_spanned(
+                path,
+                format!("`{}` is set more than once", key),
+            ));
+        }
+        self.0.push(key);
+        Ok(())
+    }
+}
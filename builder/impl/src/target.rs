@@ -0,0 +1,387 @@
+//! What a builder builds: a struct, or a single variant of an enum.
+
+use crate::is_std_collection;
+use crate::options::{FieldOptions, StructOptions};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{DeriveInput, Field, Fields, Generics, Member, Type, Variant, Visibility};
+
+pub(crate) struct Target<'a> {
+    /// `CommandBuilder`, or `ShapeCircleBuilder` for `Shape::Circle`.
+    pub builder_ty: Ident,
+    /// `CommandBuilderError`.
+    pub error_ty: Ident,
+    /// The associated function returning a new builder: `Command::builder()`,
+    /// or `Shape::circle_builder()`.
+    pub constructor: Ident,
+    /// The method finishing the builder: `build()`, or as named by
+    /// `build_fn(name = "...")`.
+    pub build_fn: Ident,
+    /// The path the built value is constructed through: `Command` or
+    /// `Shape::Circle`.
+    pub path: TokenStream,
+    pub fields: Vec<BuilderField<'a>>,
+    /// The function `build()` passes the fields to, for `#[builder]` on a
+    /// function, instead of constructing `path`.
+    pub call: Option<Call>,
+}
+
+pub(crate) struct Call {
+    /// `connect`, or `parse::<T>` for a generic function.
+    pub function: TokenStream,
+    /// `async` and `unsafe`, which `build()` takes on from the function.
+    pub qualifiers: TokenStream,
+    /// What the function returns: `build()` returns it instead of the target.
+    pub output: TokenStream,
+    /// The function is `async`, its result has to be awaited.
+    pub is_async: bool,
+    /// The `impl` block of a method or associated function, given
+    /// `#[builder]` on the block.
+    pub in_impl: Option<InImpl>,
+}
+
+pub(crate) struct InImpl {
+    /// `impl<T> Cache<T> where ...`, the block the constructor goes in.
+    pub header: TokenStream,
+    /// `Cache<T>`, which `Self` stands for.
+    pub self_ty: Type,
+    /// Generics of the constructor: those of the function, and the lifetimes
+    /// its parameters elide.
+    pub generics: Generics,
+    /// Type of the receiver of a method, which the constructor takes and the
+    /// builder holds until it passes it on.
+    pub receiver: Option<Type>,
+}
+
+pub(crate) struct BuilderField<'a> {
+    pub field: &'a Field,
+    pub opts: FieldOptions,
+    /// Name of the field on the builder and of its setter, `_0` for the first
+    /// field of a tuple struct unless named otherwise.
+    pub ident: Ident,
+    /// The field in the built value: `executable` or `0`.
+    pub member: Member,
+    /// Visibility of the setters of the field.
+    pub vis: Visibility,
+}
+
+impl<'a> Target<'a> {
+    pub fn for_struct(
+        input: &DeriveInput,
+        struct_opts: &StructOptions,
+        fields: &'a Fields,
+    ) -> syn::Result<Self> {
+        let ident = &input.ident;
+        let builder_ty = match &struct_opts.name {
+            Some(name) => name.clone(),
+            None => format_ident!("{}Builder", ident),
+        };
+        let constructor = match &struct_opts.constructor {
+            Some(constructor) if constructor == "to_builder" => {
+                return Err(syn::Error::new(
+                    constructor.span(),
+                    format!("`to_builder` clashes with another method of `{}`", ident),
+                ))
+            }
+            Some(constructor) => constructor.clone(),
+            None => Ident::new("builder", Span::call_site()),
+        };
+        Target {
+            error_ty: format_ident!("{}Error", builder_ty),
+            builder_ty,
+            constructor,
+            build_fn: build_fn(struct_opts),
+            path: quote!(#ident),
+            fields: BuilderField::all(fields, &input.vis, struct_opts)?,
+            call: None,
+        }
+        .checked(struct_opts)
+    }
+
+    pub fn for_variant(
+        input: &DeriveInput,
+        struct_opts: &StructOptions,
+        variant: &'a Variant,
+    ) -> syn::Result<Self> {
+        if let Some(attr) = variant
+            .attrs
+            .iter()
+            .find(|attr| attr.path.is_ident("builder"))
+        {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "`#[builder]` options go on the enum itself or on the fields of its variants",
+            ));
+        }
+        if let Some(name) = struct_opts
+            .name
+            .as_ref()
+            .or(struct_opts.constructor.as_ref())
+        {
+            return Err(syn::Error::new(
+                name.span(),
+                "every variant of an enum gets its own builder, named after the variant",
+            ));
+        }
+        let ident = &input.ident;
+        let variant_ident = &variant.ident;
+        let builder_ty = format_ident!("{}{}Builder", ident, variant_ident);
+        Target {
+            error_ty: format_ident!("{}Error", builder_ty),
+            builder_ty,
+            constructor: format_ident!("{}_builder", snake_case(variant_ident)),
+            build_fn: build_fn(struct_opts),
+            path: quote!(#ident::#variant_ident),
+            fields: BuilderField::all(&variant.fields, &input.vis, struct_opts)?,
+            call: None,
+        }
+        .checked(struct_opts)
+    }
+
+    /// What `build()` returns on success: `Command`, or what the function
+    /// returns.
+    pub fn output(&self, input: &DeriveInput) -> TokenStream {
+        match &self.call {
+            Some(call) => call.output.clone(),
+            None => {
+                let ident = &input.ident;
+                let (_, ty_generics, _) = input.generics.split_for_impl();
+                quote!(#ident #ty_generics)
+            }
+        }
+    }
+
+    /// `build()`'s expression making the output from the `values` of the
+    /// fields, in declaration order.
+    pub fn built(&self, values: &[TokenStream]) -> TokenStream {
+        match &self.call {
+            Some(call) => {
+                let function = &call.function;
+                let receiver = call.receiver().map(|_| quote!(self.__receiver));
+                let values = receiver.iter().chain(values);
+                let built = quote!(#function(#(#values),*));
+                if call.is_async {
+                    quote!(#built.await)
+                } else {
+                    built
+                }
+            }
+            None => {
+                let path = &self.path;
+                let member = self.fields.iter().map(|f| &f.member);
+                quote! {
+                    #path {
+                        #(#member: #values,)*
+                    }
+                }
+            }
+        }
+    }
+
+    /// Pattern destructuring a value built by `built` into `bindings`. Not for
+    /// functions, which return no such value.
+    pub fn pattern(&self, bindings: &[TokenStream]) -> TokenStream {
+        let path = &self.path;
+        let member = self.fields.iter().map(|f| &f.member);
+        quote! {
+            #path {
+                #(#member: #bindings,)*
+            }
+        }
+    }
+
+    /// Rejects builders on which two methods would get the same name.
+    fn checked(self, struct_opts: &StructOptions) -> syn::Result<Self> {
+        let mut names = vec![self.build_fn.clone()];
+        if !struct_opts.typestate {
+            for name in &["merge", "reset", "missing_fields"] {
+                names.push(Ident::new(name, Span::call_site()));
+            }
+        }
+        if struct_opts.cli {
+            names.push(Ident::new("from_args", Span::call_site()));
+        }
+        for f in &self.fields {
+            let setters = f.replace_setter().into_iter().chain(f.try_setter());
+            let mut setters = setters
+                .chain(f.opts.each.clone())
+                .chain(f.extend_setter())
+                .collect::<Vec<_>>();
+            if !struct_opts.typestate && !f.opts.setter.skip {
+                setters.extend(vec![f.is_set_method(), f.clear_method()]);
+            }
+            for setter in setters {
+                if names.iter().any(|name| name.unraw() == setter.unraw()) {
+                    return Err(syn::Error::new(
+                        setter.span(),
+                        format!(
+                            "`{}` clashes with another method of `{}`",
+                            setter.unraw(),
+                            self.builder_ty,
+                        ),
+                    ));
+                }
+                names.push(setter);
+            }
+            let constraints = f.opts.requires.iter().chain(&f.opts.conflicts_with);
+            for other in constraints {
+                let error = |message: &str| Err(syn::Error::new(other.span(), message));
+                match self
+                    .fields
+                    .iter()
+                    .find(|g| g.ident.unraw() == other.unraw())
+                {
+                    None => {
+                        return error(&format!(
+                            "`{}` has no field named `{}`",
+                            self.builder_ty,
+                            other.unraw(),
+                        ))
+                    }
+                    Some(g) if g.ident == f.ident => {
+                        return error("a field cannot require or conflict with itself")
+                    }
+                    Some(g) if g.opts.sub_builder || g.opts.setter.skip => {
+                        return error(
+                            "sub-builders and skipped fields cannot be required or conflicted with",
+                        )
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Type of the receiver the builder holds, for a method.
+    pub fn receiver(&self) -> Option<&Type> {
+        self.call.as_ref()?.receiver()
+    }
+
+    /// Some field is `set_once`, so the builder records the first one set
+    /// twice, for `build()` to report it.
+    pub fn tracks_set_twice(&self) -> bool {
+        self.fields.iter().any(|f| f.opts.set_once)
+    }
+}
+
+impl Call {
+    /// Type of the receiver the builder holds, for a method.
+    pub fn receiver(&self) -> Option<&Type> {
+        self.in_impl.as_ref()?.receiver.as_ref()
+    }
+}
+
+impl<'a> BuilderField<'a> {
+    fn all(
+        fields: &'a Fields,
+        vis: &Visibility,
+        struct_opts: &StructOptions,
+    ) -> syn::Result<Vec<Self>> {
+        fields
+            .iter()
+            .enumerate()
+            .map(|(i, field)| {
+                let opts = FieldOptions::from_field(field, struct_opts)?;
+                let (ident, member) = match &field.ident {
+                    Some(ident) => (ident.clone(), Member::Named(ident.clone())),
+                    None => (
+                        opts.name.clone().unwrap_or_else(|| format_ident!("_{}", i)),
+                        Member::Unnamed(i.into()),
+                    ),
+                };
+                Ok(BuilderField {
+                    field,
+                    vis: opts.vis.clone().unwrap_or_else(|| vis.clone()),
+                    opts,
+                    ident,
+                    member,
+                })
+            })
+            .collect()
+    }
+
+    /// Name of the setter replacing the whole value of the field, unless the
+    /// field is skipped or its `each` setter took that name.
+    pub fn replace_setter(&self) -> Option<Ident> {
+        let setter = &self.opts.setter;
+        if setter.skip {
+            return None;
+        }
+        let name = match (&setter.name, &setter.prefix) {
+            (Some(name), _) => name.clone(),
+            (None, Some(prefix)) => {
+                format_ident!("{}{}", prefix, self.ident.unraw(), span = self.ident.span())
+            }
+            (None, None) => self.ident.clone(),
+        };
+        match &self.opts.each {
+            Some(each) if *each == name => None,
+            _ => Some(name),
+        }
+    }
+
+    /// Name of the setter converting its argument with `TryInto`, `try_port`.
+    pub fn try_setter(&self) -> Option<Ident> {
+        if !self.opts.try_setter {
+            return None;
+        }
+        let name = self.replace_setter()?;
+        Some(format_ident!("try_{}", name.unraw(), span = name.span()))
+    }
+
+    /// Name of the setter adding many elements to a collection field,
+    /// `extend_args`, or `extend_0` for the first field of a tuple struct.
+    pub fn extend_setter(&self) -> Option<Ident> {
+        match &self.opts.each {
+            Some(each) => Some(self.method("extend_", "", each.span())),
+            None if !self.opts.setter.skip && is_std_collection(&self.field.ty) => {
+                Some(self.method("extend_", "", self.ident.span()))
+            }
+            None => None,
+        }
+    }
+
+    /// Name of the method telling whether the field has a value,
+    /// `is_args_set`.
+    pub fn is_set_method(&self) -> Ident {
+        self.method("is_", "_set", self.ident.span())
+    }
+
+    /// Name of the method unsetting the field, `clear_args`.
+    pub fn clear_method(&self) -> Ident {
+        self.method("clear_", "", self.ident.span())
+    }
+
+    /// A method named after the field, without the leading `_` of tuple
+    /// fields.
+    fn method(&self, prefix: &str, suffix: &str, span: Span) -> Ident {
+        let ident = self.ident.unraw().to_string();
+        let ident = ident.strip_prefix('_').unwrap_or(&ident);
+        format_ident!("{}{}{}", prefix, ident, suffix, span = span)
+    }
+}
+
+fn build_fn(struct_opts: &StructOptions) -> Ident {
+    match &struct_opts.build_fn.name {
+        Some(name) => name.clone(),
+        None => Ident::new("build", Span::call_site()),
+    }
+}
+
+/// `HttpRequest` -> `http_request`
+fn snake_case(ident: &Ident) -> String {
+    let mut res = String::new();
+    for (i, c) in ident.unraw().to_string().chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                res.push('_');
+            }
+            res.extend(c.to_lowercase());
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
@@ -0,0 +1,408 @@
+//! Code generation for `#[builder(typestate)]`.
+//!
+//! Every required field gets its own type parameter on the builder, which is
+//! `()` while the field is unset and `(T,)` once its setter has been called.
+//! The setter of a required field is only implemented for the unset state and
+//! `build()` only for the state where every required field is set, so missing
+//! (or doubly set) fields are caught by the type checker instead of at runtime.
+
+use crate::fields;
+use crate::options::StructOptions;
+use crate::target::Target;
+use crate::{
+    builder_attrs, constructor, default_value, inner_for_option, is_required, new_builder,
+    phantom_marker, setter_arg, setter_ty, to_builder, CollectionSetter,
+};
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{DeriveInput, GenericParam, Generics};
+
+pub(crate) fn expand(
+    input: &DeriveInput,
+    struct_opts: &StructOptions,
+    target: &Target,
+) -> TokenStream {
+    let ident = &input.ident;
+    let vis = &input.vis;
+    let generics = &input.generics;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let Target {
+        builder_ty,
+        build_fn,
+        fields,
+        ..
+    } = target;
+    let build_vis = struct_opts.build_fn.vis.as_ref().unwrap_or(vis);
+
+    // One state parameter per required field, in declaration order.
+    let required = fields
+        .iter()
+        .filter(|f| is_required(f, struct_opts))
+        .map(|f| {
+            let param = format!("__{}", camel_case(&f.ident));
+            (f, Ident::new(&param, f.field.span()))
+        })
+        .collect::<Vec<_>>();
+    let state_params = required.iter().map(|(_, param)| param).collect::<Vec<_>>();
+    // The receiver of a method, held by its builder.
+    let receiver = target.receiver().map(|ty| quote!(__receiver: #ty,));
+    let receiver_moved = target
+        .receiver()
+        .map(|_| quote!(__receiver: self.__receiver,));
+
+    let builder_fields = fields.iter().map(|f| {
+        let name = &f.ident;
+        let ty = &f.field.ty;
+        let ty = match required.iter().find(|(r, _)| r.ident == f.ident) {
+            Some((_, param)) => quote!(#param),
+            None if f.opts.each.is_some() || inner_for_option(ty).is_some() => quote!(#ty),
+            None => quote!(::core::option::Option<#ty>),
+        };
+        quote_spanned! {f.field.span()=>
+            #name: #ty,
+        }
+    });
+
+    let builder_init = fields.iter().map(|f| {
+        let name = &f.ident;
+        if required.iter().any(|(r, _)| r.ident == f.ident) {
+            quote_spanned! {f.field.span()=> #name: (), }
+        } else {
+            quote_spanned! {f.field.span()=> #name: ::core::default::Default::default(), }
+        }
+    });
+    let unset = type_args(generics, required.iter().map(|_| quote!(())));
+
+    let required_setters = required.iter().map(|(f, param)| {
+        let name = &f.ident;
+        let ty = &f.field.ty;
+        let other_params = state_params.iter().copied().filter(|p| *p != param);
+        let other_params = with_params(generics, other_params);
+        let (impl_generics, _, _) = other_params.split_for_impl();
+        let state_with = |state: TokenStream| {
+            let args = state_params.iter().map(|p| {
+                if *p == param {
+                    state.clone()
+                } else {
+                    quote!(#p)
+                }
+            });
+            let args = type_args(generics, args);
+            quote!(#builder_ty #args)
+        };
+        let before = state_with(quote!(()));
+        let after = state_with(quote!((#ty,)));
+        let setter_name = f.replace_setter().unwrap();
+        let setter_vis = &f.vis;
+        let (arg_ty, value) = setter_arg(f, ty, name);
+        let moved = |value: TokenStream| {
+            let field = fields.iter().map(move |other| {
+                let other = &other.ident;
+                if other == name {
+                    quote!(#other: (#value,),)
+                } else {
+                    quote!(#other: self.#other,)
+                }
+            });
+            quote! {
+                #builder_ty {
+                    #receiver_moved
+                    #(#field)*
+                    __marker: self.__marker,
+                }
+            }
+        };
+        let set = moved(value);
+        // The `extend_` setter of a collection sets the field the first time,
+        // and adds to it afterwards.
+        let (extend_unset, extend_set) = match CollectionSetter::all(f).pop() {
+            Some(CollectionSetter {
+                name: extend_name,
+                generics,
+                args,
+                where_clause: extend_where_clause,
+                items,
+            }) => {
+                let extended = moved(quote!(__collection));
+                (
+                    quote! {
+                        #setter_vis fn #extend_name #generics(self, #args) -> #after #extend_where_clause {
+                            let mut __collection: #ty = ::core::default::Default::default();
+                            ::core::iter::Extend::extend(&mut __collection, #items);
+                            #extended
+                        }
+                    },
+                    quote! {
+                        impl #impl_generics #after #where_clause {
+                            #setter_vis fn #extend_name #generics(mut self, #args) -> Self #extend_where_clause {
+                                ::core::iter::Extend::extend(&mut self.#name.0, #items);
+                                self
+                            }
+                        }
+                    },
+                )
+            }
+            None => (quote!(), quote!()),
+        };
+        quote_spanned! {f.field.span()=>
+            impl #impl_generics #before #where_clause {
+                #setter_vis fn #setter_name(self, #name: #arg_ty) -> #after {
+                    #set
+                }
+
+                #extend_unset
+            }
+
+            #extend_set
+        }
+    });
+
+    let mut optional_setters = vec![];
+    for f in fields {
+        let name = &f.ident;
+        let setter_vis = &f.vis;
+        let setter_name = f.replace_setter();
+        if f.opts.each.is_some() {
+            for collection_setter in CollectionSetter::all(f) {
+                let CollectionSetter {
+                    name: collection_name,
+                    generics,
+                    args,
+                    where_clause,
+                    items,
+                } = collection_setter;
+                optional_setters.push(quote_spanned! {f.field.span()=>
+                    #setter_vis fn #collection_name #generics(mut self, #args) -> Self #where_clause {
+                        ::core::iter::Extend::extend(&mut self.#name, #items);
+                        self
+                    }
+                });
+            }
+            let setter_name = match setter_name {
+                Some(setter_name) => setter_name,
+                None => continue,
+            };
+            let (arg_ty, value) = setter_arg(f, &f.field.ty, name);
+            optional_setters.push(quote_spanned! {f.field.span()=>
+                #setter_vis fn #setter_name(mut self, #name: #arg_ty) -> Self {
+                    self.#name = #value;
+                    self
+                }
+            });
+        } else if !is_required(f, struct_opts) {
+            for collection_setter in CollectionSetter::all(f) {
+                let CollectionSetter {
+                    name: collection_name,
+                    generics,
+                    args,
+                    where_clause,
+                    items,
+                } = collection_setter;
+                optional_setters.push(quote_spanned! {f.field.span()=>
+                    #setter_vis fn #collection_name #generics(mut self, #args) -> Self #where_clause {
+                        ::core::iter::Extend::extend(
+                            self.#name.get_or_insert_with(::core::default::Default::default),
+                            #items,
+                        );
+                        self
+                    }
+                });
+            }
+            let setter_name = match setter_name {
+                Some(setter_name) => setter_name,
+                None => continue,
+            };
+            let (ty, wrap) = setter_ty(f);
+            let (arg_ty, value) = setter_arg(f, &ty, name);
+            let value = if wrap {
+                quote!(::core::option::Option::Some(#value))
+            } else {
+                value
+            };
+            optional_setters.push(quote_spanned! {f.field.span()=>
+                #setter_vis fn #setter_name(mut self, #name: #arg_ty) -> Self {
+                    self.#name = #value;
+                    self
+                }
+            });
+        }
+    }
+
+    let built_value = fields.iter().map(|f| {
+        let name = &f.ident;
+        if required.iter().any(|(r, _)| r.ident == f.ident) {
+            return quote_spanned!(f.field.span()=> self.#name.0);
+        }
+        match default_value(f, struct_opts) {
+            Some(default) if f.opts.each.is_none() => {
+                let set = match inner_for_option(&f.field.ty) {
+                    Some(_) => quote!(::core::option::Option::Some(#name)),
+                    None => quote!(#name),
+                };
+                quote_spanned! {f.field.span()=>
+                    match self.#name {
+                        ::core::option::Option::Some(#name) => #set,
+                        ::core::option::Option::None => #default,
+                    }
+                }
+            }
+            _ => quote_spanned!(f.field.span()=> self.#name),
+        }
+    });
+    let built = target.built(&built_value.collect::<Vec<_>>());
+    let struct_default = if struct_opts.default {
+        quote!(let __default: #ident #ty_generics = ::core::default::Default::default();)
+    } else {
+        quote!()
+    };
+    let set = type_args(
+        generics,
+        required.iter().map(|(f, _)| {
+            let ty = &f.field.ty;
+            quote!((#ty,))
+        }),
+    );
+
+    let marker = phantom_marker(generics);
+    let builder_attrs = builder_attrs(struct_opts);
+
+    let all_params = with_params(generics, state_params.iter().copied());
+    let (all_impl_generics, _, _) = all_params.split_for_impl();
+    let all_args = type_args(generics, state_params.iter().map(|p| quote!(#p)));
+
+    let to_builder = to_builder(input, target, quote!(#builder_ty #set), |f, value| {
+        if required.iter().any(|(r, _)| r.ident == f.ident) {
+            quote!((#value,))
+        } else if f.opts.each.is_some() || inner_for_option(&f.field.ty).is_some() {
+            value
+        } else {
+            quote!(::core::option::Option::Some(#value))
+        }
+    });
+
+    let receiver_init = target.receiver().map(|_| quote!(__receiver: self,));
+    let constructor = constructor(
+        input,
+        target,
+        quote!(#builder_ty #unset),
+        quote! {
+            #builder_ty {
+                #receiver_init
+                #(#builder_init)*
+                __marker: ::core::marker::PhantomData,
+            }
+        },
+    );
+    let default = match target.receiver() {
+        Some(_) => quote!(),
+        None => {
+            let new_builder = new_builder(input, target);
+            quote! {
+                impl #impl_generics ::core::default::Default for #builder_ty #unset #where_clause {
+                    fn default() -> Self {
+                        #new_builder
+                    }
+                }
+            }
+        }
+    };
+    let qualifiers = target.call.as_ref().map(|call| &call.qualifiers);
+    let output = target.output(input);
+    let field_info = fields::expand(input, struct_opts, target);
+
+    quote! {
+        #to_builder
+
+        #constructor
+
+        #builder_attrs
+        #vis struct #builder_ty #all_params #where_clause {
+            #receiver
+            #(#builder_fields)*
+            __marker: ::core::marker::PhantomData<#marker>,
+        }
+
+        impl #impl_generics #builder_ty #unset #where_clause {
+            #field_info
+        }
+
+        #default
+
+        #(#required_setters)*
+
+        impl #all_impl_generics #builder_ty #all_args #where_clause {
+            #(#optional_setters)*
+        }
+
+        impl #impl_generics #builder_ty #set #where_clause {
+            #build_vis #qualifiers fn #build_fn(self) -> #output {
+                #struct_default
+                #built
+            }
+        }
+    }
+}
+
+/// The struct's own generic parameters followed by the `extra` type
+/// parameters. Defaults are dropped since they would have to come last.
+fn with_params<'a>(generics: &Generics, extra: impl IntoIterator<Item = &'a Ident>) -> Generics {
+    let mut res = generics.clone();
+    for param in &mut res.params {
+        match param {
+            GenericParam::Type(param) => {
+                param.eq_token = None;
+                param.default = None;
+            }
+            GenericParam::Const(param) => {
+                param.eq_token = None;
+                param.default = None;
+            }
+            GenericParam::Lifetime(_) => {}
+        }
+    }
+    res.params.extend(
+        extra
+            .into_iter()
+            .map(|ident| GenericParam::Type(ident.clone().into())),
+    );
+    res
+}
+
+/// `<'a, T, N, #extra...>` for a struct declaring `<'a, T, const N: usize>`.
+fn type_args(generics: &Generics, extra: impl IntoIterator<Item = TokenStream>) -> TokenStream {
+    let own = generics.params.iter().map(|param| match param {
+        GenericParam::Type(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+        GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote!(#lifetime)
+        }
+        GenericParam::Const(param) => {
+            let ident = &param.ident;
+            quote!(#ident)
+        }
+    });
+    let args = own.chain(extra);
+    quote!(<#(#args),*>)
+}
+
+/// `current_dir` -> `CurrentDir`
+pub(crate) fn camel_case(ident: &Ident) -> String {
+    ident
+        .unraw()
+        .to_string()
+        .split('_')
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
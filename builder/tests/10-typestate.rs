@@ -0,0 +1,33 @@
+// With `#[builder(typestate)]` the builder keeps track of which required
+// fields have been set in its type rather than in `Option`s checked at runtime.
+// Each required field becomes a type parameter of the builder, and `build()`
+// is only available once all of them have been set, so it returns the struct
+// directly instead of a `Result`.
+//
+// Setters in this mode take the builder by value and return the builder in its
+// next state, so they are meant to be chained.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let command: Command = Command::builder()
+        .arg("build".to_owned())
+        .env(vec![])
+        .executable("cargo".to_owned())
+        .arg("--release".to_owned())
+        .build();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert!(command.current_dir.is_none());
+}
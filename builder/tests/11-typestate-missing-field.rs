@@ -0,0 +1,19 @@
+// In typestate mode, forgetting a required field is a compile error: `build()`
+// simply does not exist until every required field has been set.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Command {
+    executable: String,
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let _command = Command::builder()
+        .args(vec![])
+        .current_dir("..".to_owned())
+        .build();
+}
@@ -0,0 +1,50 @@
+// When fields are missing, `build()` returns a `CommandBuilderError` naming all
+// of them at once rather than just the first one it came across. The error is
+// a plain enum generated next to the builder, so callers can compare it, and
+// it implements `std::error::Error` so that `?` into a boxed error still works.
+// Fields are named as written in the source, without the `r#` of raw
+// identifiers.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Token {
+    r#type: String,
+    text: String,
+}
+
+fn main() {
+    let err = Command::builder()
+        .current_dir("..".to_owned())
+        .build()
+        .unwrap_err();
+
+    assert_eq!(
+        err,
+        CommandBuilderError::MissingFields(vec!["executable".to_owned(), "env".to_owned()]),
+    );
+    assert_eq!(
+        err.to_string(),
+        "missing required fields: `executable`, `env`",
+    );
+
+    let mut builder = Token::builder();
+    builder.text("fn".to_owned());
+    assert_eq!(builder.missing_fields(), ["type"]);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        TokenBuilderError::MissingFields(vec!["type".to_owned()]),
+    );
+
+    let boxed: Box<dyn std::error::Error> = err.into();
+    assert!(boxed.is::<CommandBuilderError>());
+}
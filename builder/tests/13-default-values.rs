@@ -0,0 +1,67 @@
+// Fields marked `#[builder(default)]` are no longer required: when left unset,
+// `build()` fills them in with `Default::default()`, or with the expression
+// given in `#[builder(default = "...")]`. For `Option` fields the expression
+// gives the whole `Option`.
+//
+// On the struct itself, `#[builder(default)]` makes every field optional and
+// takes the values of those left unset from the struct's own `Default` impl.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(default)]
+    args: Vec<String>,
+    #[builder(default = "vec![\"PATH=/bin\".to_owned()]")]
+    env: Vec<String>,
+    #[builder(default = "Some(\"..\".to_owned())")]
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(default)]
+pub struct Limits {
+    open_files: u64,
+    #[builder(default = "1")]
+    processes: u64,
+    memory: Option<u64>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            open_files: 1024,
+            processes: 64,
+            memory: Some(1 << 30),
+        }
+    }
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    command: String,
+    #[builder(default = "3")]
+    retries: u32,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert!(command.args.is_empty());
+    assert_eq!(command.env, vec!["PATH=/bin"]);
+    assert_eq!(command.current_dir.as_deref(), Some(".."));
+
+    let limits = Limits::builder().open_files(1).build().unwrap();
+    assert_eq!(limits.open_files, 1);
+    assert_eq!(limits.processes, 1);
+    assert_eq!(limits.memory, Some(1 << 30));
+
+    let job = Job::builder().command("make".to_owned()).build();
+    assert_eq!(job.retries, 3);
+    let job = Job::builder().retries(5).command("make".to_owned()).build();
+    assert_eq!(job.retries, 5);
+}
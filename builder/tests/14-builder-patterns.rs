@@ -0,0 +1,53 @@
+// The receiver of the setters and of `build()` is chosen with
+// `#[builder(pattern = "...")]`:
+//
+//   - "mutable" (the default): setters take and return `&mut Self`, and
+//     `build(&mut self)` takes the values out of the builder.
+//
+//   - "owned": setters take and return `Self`, and `build(self)` consumes the
+//     builder. Builders can then be returned from functions mid-chain.
+//
+//   - "immutable": setters take `&self` and return an updated copy of the
+//     builder, and `build(&self)` clones the values, so any builder can be
+//     reused as a template. This derives `Clone` on the builder.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable")]
+pub struct Request {
+    url: String,
+    #[builder(each = "header")]
+    headers: Vec<String>,
+}
+
+fn cargo() -> CommandBuilder {
+    Command::builder().executable("cargo".to_owned())
+}
+
+fn main() {
+    let command = cargo().arg("build".to_owned()).build().unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert!(command.current_dir.is_none());
+
+    let base = Request::builder().header("Accept: */*".to_owned());
+    let first = base.url("/first".to_owned());
+    let second = base.url("/second".to_owned());
+    assert!(base.build().is_err());
+    assert_eq!(first.build().unwrap().url, "/first");
+    assert_eq!(first.build().unwrap().url, "/first");
+
+    let second = second.header("Host: example.com".to_owned()).build().unwrap();
+    assert_eq!(second.url, "/second");
+    assert_eq!(second.headers, vec!["Accept: */*", "Host: example.com"]);
+}
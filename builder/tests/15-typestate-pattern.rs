@@ -0,0 +1,12 @@
+// Typestate builders change type with every required field that is set, so
+// they can only be used by value.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate, pattern = "mutable")]
+pub struct Command {
+    executable: String,
+}
+
+fn main() {}
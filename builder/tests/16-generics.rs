@@ -0,0 +1,66 @@
+// The builder carries over the generic parameters of the struct, including
+// lifetimes, const generics, bounds, defaults and the where-clause, so that
+// generic structs can derive `Builder` like any other.
+
+use derive_builder::Builder;
+use std::fmt::Debug;
+
+pub trait Codec {
+    fn encode(&self, payload: &str) -> Vec<u8>;
+}
+
+#[derive(Clone)]
+pub struct Utf8;
+
+impl Codec for Utf8 {
+    fn encode(&self, payload: &str) -> Vec<u8> {
+        payload.as_bytes().to_vec()
+    }
+}
+
+#[derive(Builder)]
+pub struct Request<'a, T: Codec, const N: usize, E = ()>
+where
+    E: Debug,
+{
+    path: &'a str,
+    codec: T,
+    #[builder(each = "header")]
+    headers: Vec<[&'a str; N]>,
+    extra: Option<E>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Response<'a, T: Codec, const N: usize, E = ()>
+where
+    E: Debug,
+{
+    body: &'a str,
+    codec: T,
+    #[builder(each = "header")]
+    headers: Vec<[&'a str; N]>,
+    extra: Option<E>,
+}
+
+fn main() {
+    let path = String::from("/index.html");
+    let request: Request<Utf8, 2> = Request::builder()
+        .path(&path)
+        .codec(Utf8)
+        .header(["Accept", "*/*"])
+        .build()
+        .unwrap();
+    assert_eq!(request.codec.encode(request.path), b"/index.html");
+    assert_eq!(request.headers, vec![["Accept", "*/*"]]);
+    assert!(request.extra.is_none());
+
+    let response = Response::builder()
+        .codec(Utf8)
+        .extra(404)
+        .body("not found")
+        .header(["Content-Length", "9"])
+        .build();
+    assert_eq!(response.codec.encode(response.body), b"not found");
+    assert_eq!(response.extra, Some(404));
+}
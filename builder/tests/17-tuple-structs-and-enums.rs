@@ -0,0 +1,62 @@
+// Tuple structs get positional setters named `_0`, `_1`, ... unless a field is
+// given a name with `#[builder(name = "...")]`.
+//
+// Enums get one builder per variant with fields, created through an
+// associated function named after the variant: `Shape::circle_builder()`
+// returns a `ShapeCircleBuilder` whose `build()` returns a `Shape`.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Rgb(u8, #[builder(name = "green")] u8, u8);
+
+#[derive(Builder, Debug, PartialEq)]
+pub enum Shape<T> {
+    Circle {
+        radius: T,
+        center: Option<(T, T)>,
+    },
+    Polygon(#[builder(each = "point")] Vec<(T, T)>),
+    Empty,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub enum Message<'a> {
+    Text(&'a str),
+    Ping { id: u32 },
+}
+
+fn main() {
+    let Rgb(r, g, b) = Rgb::builder()._0(255).green(128)._2(0).build().unwrap();
+    assert_eq!((r, g, b), (255, 128, 0));
+
+    let circle = Shape::circle_builder().radius(1.5).build().unwrap();
+    assert_eq!(
+        circle,
+        Shape::Circle {
+            radius: 1.5,
+            center: None,
+        },
+    );
+
+    let triangle = Shape::polygon_builder()
+        .point((0, 0))
+        .point((0, 1))
+        .point((1, 0))
+        .build()
+        .unwrap();
+    assert_eq!(triangle, Shape::Polygon(vec![(0, 0), (0, 1), (1, 0)]));
+
+    let err = Shape::<u8>::circle_builder().build().unwrap_err();
+    assert_eq!(err, ShapeCircleBuilderError::MissingFields(vec!["radius".to_owned()]));
+
+    match Message::text_builder()._0("hello").build() {
+        Message::Text(text) => assert_eq!(text, "hello"),
+        Message::Ping { .. } => unreachable!(),
+    }
+    match Message::ping_builder().id(7).build() {
+        Message::Ping { id } => assert_eq!(id, 7),
+        Message::Text(_) => unreachable!(),
+    }
+}
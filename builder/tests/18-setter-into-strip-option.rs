@@ -0,0 +1,43 @@
+// `#[builder(setter(into))]` makes a setter accept anything implementing
+// `Into` the field's type, so that `.executable("cargo")` works for a `String`
+// field. For `each` fields it applies to the per-item setter as well.
+//
+// Setters of `Option<T>` fields take a `T` by default. With
+// `#[builder(setter(strip_option = false))]` they take the `Option<T>` itself,
+// which lets callers pass along an `Option` they already have.
+//
+// Both can also be set on the struct, as defaults for all of its fields.
+
+use derive_builder::Builder;
+use std::path::PathBuf;
+
+#[derive(Builder)]
+#[builder(setter(into))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(setter(into = false))]
+    env: Vec<(String, String)>,
+    #[builder(setter(strip_option = false))]
+    current_dir: Option<PathBuf>,
+    timeout: Option<u64>,
+}
+
+fn main() {
+    let dir: Option<&str> = Some("..");
+    let command = Command::builder()
+        .executable("cargo")
+        .arg("build")
+        .arg(String::from("--release"))
+        .env(vec![("RUST_LOG".to_owned(), "debug".to_owned())])
+        .current_dir(dir.map(PathBuf::from))
+        .timeout(30u8)
+        .build()
+        .unwrap();
+
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build", "--release"]);
+    assert_eq!(command.current_dir, Some(PathBuf::from("..")));
+    assert_eq!(command.timeout, Some(30));
+}
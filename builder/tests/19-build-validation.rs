@@ -0,0 +1,88 @@
+// `#[builder(build_fn(validate = "path::to_fn"))]` checks invariants spanning
+// several fields. The function receives the assembled value before `build()`
+// returns it, and any error it returns is reported as
+// `ServerBuilderError::Validation` with the error's `Display` output. A failed
+// validation leaves the builder as it was, so it can be fixed and built again,
+// sub-builders included: a field which was only defaulted is still unset.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(build_fn(validate = "checks::validate_server"))]
+pub struct Server {
+    host: String,
+    #[builder(default)]
+    tls: bool,
+    port: Option<u16>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Upstream {
+    host: String,
+    #[builder(default = "80", set_once)]
+    port: u16,
+}
+
+#[derive(Builder, Debug)]
+#[builder(build_fn(validate = "checks::validate_proxy"))]
+pub struct Proxy {
+    #[builder(sub_builder)]
+    upstream: Upstream,
+}
+
+mod checks {
+    pub fn validate_server(server: &super::Server) -> Result<(), String> {
+        if server.tls && server.port.is_none() {
+            return Err("port must be set when tls is enabled".to_owned());
+        }
+        Ok(())
+    }
+
+    pub fn validate_proxy(proxy: &super::Proxy) -> Result<(), String> {
+        if proxy.upstream.port == 80 {
+            return Err("the upstream must not be plain http".to_owned());
+        }
+        Ok(())
+    }
+}
+
+fn main() {
+    let server = Server::builder()
+        .host("localhost".to_owned())
+        .build()
+        .unwrap();
+    assert!(!server.tls);
+
+    let err = Server::builder()
+        .host("localhost".to_owned())
+        .tls(true)
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::Validation("port must be set when tls is enabled".to_owned()),
+    );
+    assert_eq!(
+        err.to_string(),
+        "validation failed: port must be set when tls is enabled",
+    );
+
+    let mut builder = Server::builder();
+    builder.host("localhost".to_owned()).tls(true);
+    assert!(builder.build().is_err());
+    assert!(builder.is_host_set() && builder.is_tls_set());
+    let server = builder.port(443).build().unwrap();
+    assert_eq!(server.host, "localhost");
+    assert!(server.tls);
+    assert_eq!(server.port, Some(443));
+
+    let mut builder = Proxy::builder();
+    builder.upstream().host("backend".to_owned());
+    assert!(builder.build().is_err());
+    assert!(builder.upstream().is_host_set());
+    assert!(!builder.upstream().is_port_set());
+    builder.upstream().port(8080);
+    let proxy = builder.build().unwrap();
+    assert_eq!(proxy.upstream.host, "backend");
+    assert_eq!(proxy.upstream.port, 8080);
+}
@@ -0,0 +1,30 @@
+// Attributes other than `#[builder(...)]` on the struct or its fields, such as
+// doc comments or attributes for other derives, are left alone and may come in
+// any order relative to the builder's own.
+
+use derive_builder::Builder;
+
+/// A process to spawn.
+#[derive(Builder)]
+#[allow(dead_code)]
+pub struct Command {
+    /// The program to run.
+    executable: String,
+    /// Arguments passed to the program.
+    #[builder(each = "arg")]
+    #[allow(unused)]
+    args: Vec<String>,
+    #[cfg_attr(all(), allow(unused))]
+    /// Working directory of the process.
+    #[builder(default)]
+    current_dir: Option<String>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(command.args, vec!["build"]);
+}
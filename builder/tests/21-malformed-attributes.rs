@@ -0,0 +1,89 @@
+// Every malformed `#[builder]` attribute is reported at the offending tokens,
+// rather than being ignored or making the macro panic.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct EachOnArray {
+    #[builder(each = "arg")]
+    args: [String; 2],
+}
+
+#[derive(Builder)]
+pub struct SetTwice {
+    #[builder(each = "arg")]
+    #[builder(default, each = "argument")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct NotAnIdent {
+    #[builder(each = "an arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(typestat)]
+pub struct UnknownOption {
+    executable: String,
+}
+
+#[derive(Builder)]
+#[builder(pattern)]
+pub struct MissingValue {
+    executable: String,
+}
+
+#[derive(Builder)]
+pub enum OptionsOnVariant {
+    #[builder(default)]
+    Variant { value: u8 },
+}
+
+#[derive(Builder)]
+#[builder(name = "ShapeBuilder")]
+pub enum NamedEnumBuilder {
+    Circle { radius: f64 },
+}
+
+#[derive(Builder)]
+pub struct SubBuilderOfVec {
+    #[builder(sub_builder, each = "arg")]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct TrySetterOfVec {
+    #[builder(each = "arg", try_setter)]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(cli)]
+pub struct Positional(String);
+
+#[derive(Builder)]
+#[builder(cli)]
+pub struct CliVecWithoutEach {
+    files: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct SetOnceOfVec {
+    #[builder(each = "arg", set_once)]
+    args: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct RequiresUnknownField {
+    #[builder(requires = "password")]
+    user: Option<String>,
+}
+
+#[derive(Builder)]
+pub struct ConflictsWithItself {
+    #[builder(conflicts_with = "verbose")]
+    verbose: bool,
+}
+
+fn main() {}
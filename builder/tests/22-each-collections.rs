@@ -0,0 +1,65 @@
+// Besides `Vec`, `#[builder(each = "...")]` works on the other std collections
+// and on any collection of your own which is `Default + Extend`. The one at a
+// time setter of a `HashMap` or `BTreeMap` takes a key and a value.
+
+use derive_builder::Builder;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+#[derive(Default)]
+pub struct Flags(Vec<String>);
+
+impl Extend<&'static str> for Flags {
+    fn extend<I: IntoIterator<Item = &'static str>>(&mut self, iter: I) {
+        self.0.extend(iter.into_iter().map(|flag| format!("--{}", flag)));
+    }
+}
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: VecDeque<String>,
+    #[builder(each = "env", setter(into))]
+    env: BTreeMap<String, String>,
+    #[builder(each = "feature")]
+    features: HashSet<&'static str>,
+    #[builder(each = "flag")]
+    flags: Flags,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    name: String,
+    #[builder(each = "tag")]
+    tags: BTreeSet<u8>,
+    #[builder(each = "env")]
+    env: std::collections::HashMap<String, u16>,
+}
+
+fn main() {
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .env("RUST_LOG", "debug")
+        .env("CARGO_HOME", String::from("/tmp"))
+        .feature("serde")
+        .feature("serde")
+        .flag("release")
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["build"]);
+    assert_eq!(command.env["RUST_LOG"], "debug");
+    assert_eq!(command.env.keys().collect::<Vec<_>>(), ["CARGO_HOME", "RUST_LOG"]);
+    assert_eq!(command.features.len(), 1);
+    assert_eq!(command.flags.0, ["--release"]);
+
+    let job = Job::builder()
+        .tag(2)
+        .tag(1)
+        .name("test".to_owned())
+        .env("PORT".to_owned(), 8080)
+        .build();
+    assert_eq!(job.tags.into_iter().collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(job.env["PORT"], 8080);
+}
@@ -0,0 +1,74 @@
+// Every collection field gets an `extend_<field>` setter adding all the
+// elements of an iterator at once, next to the setter replacing the whole
+// collection and, with `#[builder(each = "...")]`, the one at a time setter.
+// Without `each` this applies to the collections of the standard library, and
+// a required collection counts as set once extended.
+
+use derive_builder::Builder;
+use std::collections::BTreeMap;
+
+#[derive(Builder)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env", setter(into))]
+    env: BTreeMap<String, String>,
+    features: Vec<String>,
+    #[builder(default)]
+    jobs: BTreeMap<String, u32>,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Job {
+    steps: Vec<String>,
+    #[builder(default)]
+    tags: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Pipeline {
+    #[builder(each = "stage")]
+    stages: Vec<u32>,
+}
+
+fn main() {
+    let parsed = vec!["build".to_owned(), "--release".to_owned()];
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("+nightly".to_owned())
+        .extend_args(parsed)
+        .env("RUST_LOG", "debug")
+        .extend_env(vec![("CARGO_HOME", "/tmp"), ("RUSTFLAGS", "")])
+        .extend_features(vec!["serde".to_owned()])
+        .extend_features(vec!["std".to_owned()])
+        .extend_jobs(vec![("check".to_owned(), 2)])
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["+nightly", "build", "--release"]);
+    assert_eq!(command.env.len(), 3);
+    assert_eq!(command.features, ["serde", "std"]);
+    assert_eq!(command.jobs["check"], 2);
+
+    let command = Command::builder()
+        .executable("cargo".to_owned())
+        .arg("run".to_owned())
+        .args(vec!["test".to_owned()])
+        .features(vec![])
+        .build()
+        .unwrap();
+    assert_eq!(command.args, ["test"]);
+
+    let pipeline = Pipeline::builder().extend_stages(1..3).stage(3).build().unwrap();
+    assert_eq!(pipeline.stages, [1, 2, 3]);
+
+    let job = Job::builder()
+        .extend_steps(vec!["checkout".to_owned()])
+        .extend_steps(vec!["test".to_owned()])
+        .extend_tags(vec!["ci".to_owned()])
+        .build();
+    assert_eq!(job.steps, ["checkout", "test"]);
+    assert_eq!(job.tags, ["ci"]);
+}
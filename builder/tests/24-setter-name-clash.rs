@@ -0,0 +1,45 @@
+// Setters are never silently left out when their names clash, either with each
+// other or with `build`, however it is named. The compiler error points at the
+// name given twice.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct EachClash {
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    arg: String,
+}
+
+#[derive(Builder)]
+pub struct ExtendClash {
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    extend_args: bool,
+}
+
+#[derive(Builder)]
+pub struct BuildClash {
+    #[builder(each = "build")]
+    steps: Vec<String>,
+}
+
+#[derive(Builder)]
+pub struct RenameClash {
+    #[builder(setter(name = "merge"))]
+    steps: Vec<String>,
+}
+
+#[derive(Builder)]
+#[builder(build_fn(name = "finish"))]
+pub struct BuildFnClash {
+    finish: bool,
+}
+
+#[derive(Builder)]
+#[builder(constructor = "to_builder")]
+pub struct ConstructorClash {
+    executable: String,
+}
+
+fn main() {}
@@ -0,0 +1,154 @@
+// A field whose type derives `Builder` as well can be marked with
+// `#[builder(sub_builder)]`. The parent builder then stores the field's own
+// builder, hands it out by `&mut` through a method named after the field, and
+// builds it as part of `build()`.
+//
+// Errors of a sub-builder name the fields through the parent's field, like
+// `server.port`. `build()` reports the missing fields of the parent and of all
+// its sub-builders at once. The parent and its sub-builders may each use any pattern, and
+// may be defined in different modules or crates, like `limits` below: the
+// errors are `#[non_exhaustive]`, so they are nested through a trait rather
+// than by matching on them.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+pub struct Tls {
+    cert: String,
+    key: String,
+}
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(build_fn(validate = "Server::check"))]
+pub struct Server {
+    host: String,
+    port: u16,
+    #[builder(sub_builder)]
+    tls: Tls,
+}
+
+impl Server {
+    fn check(&self) -> Result<(), String> {
+        if self.port == 0 {
+            return Err("port 0 is reserved".to_owned());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder, Debug)]
+pub struct Deployment {
+    name: String,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Builder, Debug)]
+pub struct Cluster {
+    name: String,
+    #[builder(sub_builder)]
+    primary: Server,
+    #[builder(sub_builder)]
+    backup: Server,
+}
+
+mod limits {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug)]
+    #[builder(pattern = "owned")]
+    pub struct Memory {
+        pub max: u64,
+    }
+
+    #[derive(Builder, Debug, Clone)]
+    #[builder(pattern = "immutable", derive(Clone))]
+    pub struct Cpu {
+        pub cores: u8,
+    }
+}
+
+#[derive(Builder, Debug)]
+pub struct Container {
+    #[builder(sub_builder)]
+    memory: limits::Memory,
+    #[builder(sub_builder)]
+    cpu: limits::Cpu,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Pod {
+    name: String,
+    #[builder(sub_builder)]
+    container: Container,
+}
+
+fn main() {
+    let mut builder = Deployment::builder();
+    builder.name("web".to_owned());
+    builder.server().host("localhost".to_owned()).port(8080);
+    builder.server().tls().cert("cert.pem".to_owned());
+
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err,
+        DeploymentBuilderError::MissingFields(vec!["server.tls.key".to_owned()]),
+    );
+    assert_eq!(err.to_string(), "missing required fields: `server.tls.key`");
+
+    builder.server().tls().key("key.pem".to_owned());
+    let deployment = builder.build().unwrap();
+    assert_eq!(deployment.server.port, 8080);
+    assert_eq!(deployment.server.tls.key, "key.pem");
+
+    let mut builder = Deployment::builder();
+    builder.name("web".to_owned());
+    builder
+        .server()
+        .host("localhost".to_owned())
+        .port(0)
+        .tls()
+        .cert("cert.pem".to_owned())
+        .key("key.pem".to_owned());
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err,
+        DeploymentBuilderError::Validation("server: port 0 is reserved".to_owned()),
+    );
+
+    // The memory is built before the missing `cpu.cores` is found, and given
+    // back to its builder.
+    let mut builder = Container::builder();
+    *builder.memory() = limits::Memory::builder().max(1 << 30);
+    let err = builder.build().unwrap_err();
+    assert_eq!(
+        err,
+        ContainerBuilderError::MissingFields(vec!["cpu.cores".to_owned()]),
+    );
+    *builder.cpu() = builder.cpu().cores(4);
+    let container = builder.build().unwrap();
+    assert_eq!(container.memory.max, 1 << 30);
+    assert_eq!(container.cpu.cores, 4);
+
+    let mut pod = Pod::builder().name("web".to_owned());
+    *pod.container().memory() = limits::Memory::builder().max(1 << 20);
+    *pod.container().cpu() = limits::Cpu::builder().cores(1);
+    let pod = pod.build().unwrap();
+    assert_eq!(pod.container.cpu.cores, 1);
+
+    let mut cluster = Cluster::builder();
+    cluster.primary().host("a".to_owned()).tls().key("a.key".to_owned());
+    cluster.backup().port(8080);
+    assert_eq!(
+        cluster.build().unwrap_err(),
+        ClusterBuilderError::MissingFields(vec![
+            "name".to_owned(),
+            "primary.port".to_owned(),
+            "primary.tls.cert".to_owned(),
+            "backup.host".to_owned(),
+            "backup.tls.cert".to_owned(),
+            "backup.tls.key".to_owned(),
+        ]),
+    );
+}
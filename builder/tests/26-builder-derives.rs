@@ -0,0 +1,51 @@
+// Builders implement `Default`, starting out like the constructor's. Traits
+// listed in `#[builder(derive(...))]` are derived on the builder struct and the
+// attributes in `#[builder(struct_attrs(...))]` are put on it, which allows
+// keeping half-built builders around as templates.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug, PartialEq)]
+#[builder(derive(Clone, Debug, PartialEq))]
+#[builder(struct_attrs(doc = "Builds a [`Command`].", must_use))]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "immutable", derive(Clone, Debug))]
+pub struct Request {
+    url: String,
+}
+
+#[derive(Builder)]
+#[builder(typestate, derive(Debug))]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let mut template = CommandBuilder::default();
+    template.executable("cargo".to_owned()).arg("build".to_owned());
+    assert_eq!(template, template.clone());
+    assert_ne!(template, CommandBuilder::default());
+
+    let release = template.clone().arg("--release".to_owned()).build().unwrap();
+    let debug = template.clone().build().unwrap();
+    assert_eq!(release.args, ["build", "--release"]);
+    assert_eq!(debug.args, ["build"]);
+
+    let debug = format!("{:?}", template);
+    assert!(debug.starts_with("CommandBuilder {"));
+
+    let request = RequestBuilder::default().url("/".to_owned());
+    assert_eq!(request.clone().build().unwrap().url, "/");
+
+    let point = PointBuilder::default().x(1);
+    assert!(format!("{:?}", point).contains("x: (1,)"));
+    assert_eq!(point.y(2).build().y, 2);
+}
@@ -0,0 +1,60 @@
+// Builders can be layered: `merge` overlays the fields set on another builder
+// onto this one, so that later sources override earlier ones while fields the
+// other builder left unset keep their value. Sub-builders are merged field by
+// field as well.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder)]
+pub struct Config {
+    name: String,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    log_level: Option<String>,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Builder)]
+#[builder(pattern = "owned")]
+pub struct Flags {
+    verbose: bool,
+    quiet: bool,
+}
+
+fn main() {
+    let mut defaults = Config::builder();
+    defaults
+        .name("app".to_owned())
+        .tag("default".to_owned())
+        .log_level("info".to_owned());
+    defaults.server().host("0.0.0.0".to_owned()).port(80);
+
+    let mut user = Config::builder();
+    user.log_level("debug".to_owned());
+    user.server().port(8080);
+
+    let mut cli = Config::builder();
+    cli.tag("cli".to_owned()).tag("local".to_owned());
+
+    let config = defaults.merge(user).merge(cli).build().unwrap();
+    assert_eq!(config.name, "app");
+    assert_eq!(config.tags, ["cli", "local"]);
+    assert_eq!(config.log_level.as_deref(), Some("debug"));
+    assert_eq!(config.server.host, "0.0.0.0");
+    assert_eq!(config.server.port, 8080);
+
+    let flags = Flags::builder()
+        .verbose(true)
+        .quiet(false)
+        .merge(Flags::builder().quiet(true))
+        .build()
+        .unwrap();
+    assert!(flags.verbose && flags.quiet);
+}
@@ -0,0 +1,53 @@
+// With the `serde` feature enabled, `#[builder(serde)]` derives
+// `serde::Deserialize` on a runtime builder, with every field optional.
+// Partial configurations can be deserialized into builders, merged, and built
+// once. A sub-builder needs the option on its own struct too.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(serde)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder, Debug)]
+#[builder(serde)]
+pub struct Config {
+    name: String,
+    #[builder(each = "tag")]
+    tags: Vec<String>,
+    log_level: Option<String>,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {
+    let defaults: ConfigBuilder = serde_json::from_str(
+        r#"{ "name": "app", "log_level": "info", "server": { "host": "0.0.0.0", "port": 80 } }"#,
+    )
+    .unwrap();
+    let user: ConfigBuilder =
+        serde_json::from_str(r#"{ "tags": ["web"], "server": { "port": 8080 } }"#).unwrap();
+
+    let mut builder = defaults;
+    builder.merge(user);
+    let config = builder.build().unwrap();
+    assert_eq!(config.name, "app");
+    assert_eq!(config.tags, ["web"]);
+    assert_eq!(config.log_level.as_deref(), Some("info"));
+    assert_eq!(config.server.host, "0.0.0.0");
+    assert_eq!(config.server.port, 8080);
+
+    let mut empty: ConfigBuilder = serde_json::from_str("{}").unwrap();
+    let err = empty.build().unwrap_err();
+    assert_eq!(
+        err,
+        ConfigBuilderError::MissingFields(vec![
+            "name".to_owned(),
+            "server.host".to_owned(),
+            "server.port".to_owned(),
+        ]),
+    );
+}
@@ -0,0 +1,124 @@
+// A field with `#[builder(env = "...")]` left unset is parsed from the given
+// environment variable through `FromStr` by `build()`. Explicitly set values
+// win over the environment, which wins over defaults. A required field is only
+// missing if the variable is not set either.
+//
+// A variable which does not parse is reported as an `InvalidEnvVar` error
+// naming the field and the variable, and so is one rejected by the field's
+// `validate` function, which checks it like a value given to the setter. The
+// environment is read through absolute paths, so a module named `std` next to
+// the struct is no obstacle.
+
+use derive_builder::Builder;
+use std::env;
+
+#[derive(Builder, Debug)]
+pub struct Server {
+    #[builder(env = "TEST_SERVER_HOST")]
+    host: String,
+    #[builder(env = "TEST_SERVER_PORT", default = "80")]
+    port: u16,
+    #[builder(env = "TEST_SERVER_WORKERS")]
+    workers: Option<usize>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Probe {
+    #[builder(env = "TEST_PROBE_PORT", validate = "unreserved")]
+    port: u16,
+    #[builder(env = "TEST_PROBE_RETRIES", validate = "few", setter(strip_option = false))]
+    retries: Option<u8>,
+}
+
+fn unreserved(port: &u16) -> Result<(), String> {
+    if *port < 1024 {
+        return Err(format!("port {} is reserved", port));
+    }
+    Ok(())
+}
+
+fn few(retries: &Option<u8>) -> Result<(), String> {
+    match retries {
+        Some(retries) if *retries > 5 => Err(format!("{} retries are too many", retries)),
+        _ => Ok(()),
+    }
+}
+
+mod worker {
+    use derive_builder::Builder;
+
+    mod std {}
+
+    #[derive(Builder, Debug)]
+    pub struct Worker {
+        #[builder(env = "TEST_WORKER_THREADS", default = "1")]
+        pub threads: usize,
+    }
+}
+
+#[derive(Builder, Debug)]
+pub struct App {
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {
+    let err = Server::builder().build().unwrap_err();
+    assert_eq!(err, ServerBuilderError::MissingFields(vec!["host".to_owned()]));
+
+    env::set_var("TEST_SERVER_HOST", "example.com");
+    let server = Server::builder().build().unwrap();
+    assert_eq!(server.host, "example.com");
+    assert_eq!(server.port, 80);
+    assert_eq!(server.workers, None);
+
+    env::set_var("TEST_SERVER_PORT", "8080");
+    env::set_var("TEST_SERVER_WORKERS", "4");
+    let server = Server::builder().host("localhost".to_owned()).build().unwrap();
+    assert_eq!(server.host, "localhost");
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, Some(4));
+
+    env::set_var("TEST_SERVER_PORT", "http");
+    let err = Server::builder().build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid `port` from environment variable `TEST_SERVER_PORT`: invalid digit found in string",
+    );
+    assert!(Server::builder().port(443).build().is_ok());
+
+    let err = App::builder().build().unwrap_err();
+    assert_eq!(
+        err,
+        AppBuilderError::InvalidEnvVar {
+            field: "server.port".to_owned(),
+            var: "TEST_SERVER_PORT".to_owned(),
+            reason: "invalid digit found in string".to_owned(),
+        },
+    );
+
+    env::set_var("TEST_PROBE_PORT", "0");
+    let err = Probe::builder().build().unwrap_err();
+    assert_eq!(
+        err,
+        ProbeBuilderError::InvalidEnvVar {
+            field: "port".to_owned(),
+            var: "TEST_PROBE_PORT".to_owned(),
+            reason: "port 0 is reserved".to_owned(),
+        },
+    );
+    env::set_var("TEST_PROBE_PORT", "8080");
+    env::set_var("TEST_PROBE_RETRIES", "9");
+    let err = Probe::builder().build().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid `retries` from environment variable `TEST_PROBE_RETRIES`: 9 retries are too many",
+    );
+    env::set_var("TEST_PROBE_RETRIES", "3");
+    let probe = Probe::builder().build().unwrap();
+    assert_eq!((probe.port, probe.retries), (8080, Some(3)));
+
+    env::set_var("TEST_WORKER_THREADS", "8");
+    let worker = worker::Worker::builder().build().unwrap();
+    assert_eq!(worker.threads, 8);
+}
@@ -0,0 +1,149 @@
+// With `#[builder(cli)]`, the builder can be filled from command-line flags
+// through `from_args`. Every field gets a `--field-name` flag taking a value,
+// `each` fields a flag named after their setter which may be repeated, and
+// `bool` fields a switch. `--help` returns no builder, for the caller to print
+// the help text made of the doc comments. Only the errors of `cli` builders
+// have an `InvalidArgs` variant. Values go through the validators of their
+// fields like those given to the setters.
+
+use derive_builder::Builder;
+use std::collections::BTreeMap;
+
+/// Runs a program.
+#[derive(Builder, Debug)]
+#[builder(cli, derive(Debug))]
+pub struct Command {
+    /// The program to run.
+    executable: String,
+    /// Arguments passed to the program.
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    /// Variables added to the environment.
+    #[builder(each = "env")]
+    env: BTreeMap<String, String>,
+    /// Directory to run the program in.
+    ///
+    /// Defaults to the current directory.
+    current_dir: Option<String>,
+    /// Seconds to wait for the program.
+    #[builder(default = "30")]
+    timeout: u32,
+    /// Print what is being run.
+    verbose: bool,
+}
+
+#[derive(Builder, Debug)]
+#[builder(cli, derive(Debug))]
+pub struct Serve {
+    #[builder(validate = "unreserved")]
+    port: u16,
+    builder: Option<String>,
+}
+
+fn unreserved(port: &u16) -> Result<(), &'static str> {
+    if *port == 0 {
+        Err("port 0 is reserved")
+    } else {
+        Ok(())
+    }
+}
+
+/// A builder without `cli` can still hold one with it.
+#[derive(Builder, Debug)]
+pub struct Job {
+    name: String,
+    #[builder(sub_builder)]
+    command: Command,
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn main() {
+    let command = CommandBuilder::from_args(args(&[
+        "--executable",
+        "cargo",
+        "--arg",
+        "build",
+        "--arg=--release",
+        "--env",
+        "RUST_LOG=debug",
+        "--current-dir",
+        "/tmp",
+    ]))
+    .unwrap()
+    .unwrap()
+    .build()
+    .unwrap();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, ["build", "--release"]);
+    assert_eq!(command.env["RUST_LOG"], "debug");
+    assert_eq!(command.current_dir.as_deref(), Some("/tmp"));
+    assert_eq!(command.timeout, 30);
+    assert!(!command.verbose);
+
+    let mut builder = CommandBuilder::from_args(args(&["--verbose", "--timeout", "5"]))
+        .unwrap()
+        .unwrap();
+    let command = builder.executable("ls".to_owned()).build().unwrap();
+    assert!(command.verbose);
+    assert_eq!(command.timeout, 5);
+    assert_eq!(command.current_dir, None);
+
+    let err = CommandBuilder::from_args(args(&["--timeout", "soon"])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid arguments: invalid value for `--timeout`: invalid digit found in string",
+    );
+    let err = CommandBuilder::from_args(args(&["--executable"])).unwrap_err();
+    assert_eq!(
+        err,
+        CommandBuilderError::InvalidArgs("missing value for `--executable`".to_owned()),
+    );
+    let err = CommandBuilder::from_args(args(&["build"])).unwrap_err();
+    assert_eq!(
+        err,
+        CommandBuilderError::InvalidArgs("unexpected argument `build`".to_owned()),
+    );
+
+    let help = "\
+Runs a program.
+
+Options:
+  --executable <EXECUTABLE>    The program to run. (required)
+  --arg <ARG>                  Arguments passed to the program. (repeatable)
+  --env <KEY=VALUE>            Variables added to the environment. (repeatable)
+  --current-dir <CURRENT_DIR>  Directory to run the program in.
+  --timeout <TIMEOUT>          Seconds to wait for the program.
+  --verbose                    Print what is being run.
+  -h, --help                   Print this help text.
+";
+    assert_eq!(CommandBuilder::HELP, help);
+    let builder = CommandBuilder::from_args(args(&["--executable", "ls", "--help"])).unwrap();
+    assert!(builder.is_none());
+
+    let mut job = Job::builder();
+    job.name("list".to_owned());
+    *job.command() = CommandBuilder::from_args(args(&["--verbose"])).unwrap().unwrap();
+    assert_eq!(
+        job.build().unwrap_err(),
+        JobBuilderError::MissingFields(vec!["command.executable".to_owned()]),
+    );
+
+    let err = ServeBuilder::from_args(args(&["--port", "0"])).unwrap_err();
+    assert_eq!(
+        err,
+        ServeBuilderError::InvalidValue {
+            field: "port".to_owned(),
+            reason: "port 0 is reserved".to_owned(),
+        },
+    );
+    let serve = ServeBuilder::from_args(args(&["--port=8080", "--builder", "docker"]))
+        .unwrap()
+        .unwrap()
+        .build()
+        .unwrap();
+    assert_eq!(serve.port, 8080);
+    assert_eq!(serve.builder.as_deref(), Some("docker"));
+}
@@ -0,0 +1,75 @@
+// A built value converts back into a builder with every field set, either by
+// value through `From` or by cloning its fields through `to_builder()`, which
+// makes it easy to copy a value while changing one field.
+//
+// `to_builder()` is only callable when every field is `Clone`; a struct with
+// other fields still derives, without it.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Clone, Debug)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Point<T> {
+    x: T,
+    y: T,
+    label: Option<String>,
+}
+
+pub struct Handle;
+
+#[derive(Builder)]
+pub struct Connection {
+    handle: Handle,
+    retries: u32,
+}
+
+fn main() {
+    let mut builder = Command::builder();
+    builder
+        .executable("cargo".to_owned())
+        .arg("build".to_owned())
+        .current_dir("..".to_owned());
+    builder.server().host("localhost".to_owned()).port(80);
+    let command = builder.build().unwrap();
+
+    let mut builder = command.to_builder();
+    builder.arg("--release".to_owned()).server().port(8080);
+    let release = builder.build().unwrap();
+    assert_eq!(release.executable, "cargo");
+    assert_eq!(release.args, ["build", "--release"]);
+    assert_eq!(release.current_dir.as_deref(), Some(".."));
+    assert_eq!(release.server.port, 8080);
+    assert_eq!(command.server.port, 80);
+
+    let rebuilt = CommandBuilder::from(command)
+        .executable("rustc".to_owned())
+        .build()
+        .unwrap();
+    assert_eq!(rebuilt.executable, "rustc");
+    assert_eq!(rebuilt.args, ["build"]);
+
+    let point = Point::builder().x(1).y(2).label("origin".to_owned()).build();
+    let moved = point.to_builder().label("moved".to_owned()).build();
+    assert_eq!((moved.x, moved.y), (1, 2));
+    assert_eq!(moved.label.as_deref(), Some("moved"));
+
+    let connection = Connection::builder().handle(Handle).retries(3).build().unwrap();
+    let retried = ConnectionBuilder::from(connection).retries(5).build().unwrap();
+    assert_eq!(retried.retries, 5);
+}
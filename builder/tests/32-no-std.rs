@@ -0,0 +1,68 @@
+// With `#[builder(no_std)]`, generated code only refers to `core` and `alloc`,
+// so that builders can be derived in `#![no_std]` crates which link `alloc`.
+// The error type implements `core::error::Error` rather than its `std`
+// re-export.
+
+#![no_std]
+
+extern crate alloc;
+// Only for the test harness to have a `main`, under another name so that the
+// generated code cannot refer to `::std`.
+extern crate std as test_std;
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+#[builder(no_std, build_fn(validate = "Sensor::check"))]
+pub struct Sensor {
+    name: String,
+    #[builder(each = "reading")]
+    readings: Vec<i32>,
+    #[builder(each = "tag")]
+    tags: BTreeMap<String, String>,
+    #[builder(default = "100")]
+    interval_ms: u32,
+    calibration: Option<i32>,
+}
+
+impl Sensor {
+    fn check(&self) -> Result<(), &'static str> {
+        if self.interval_ms == 0 {
+            return Err("interval must not be zero");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Builder, Debug)]
+#[builder(no_std)]
+pub struct Board {
+    #[builder(sub_builder)]
+    sensor: Sensor,
+}
+
+fn main() {
+    let sensor = Sensor::builder()
+        .name("thermo".to_string())
+        .reading(21)
+        .extend_readings([22, 23])
+        .tag("unit".to_string(), "celsius".to_string())
+        .build()
+        .unwrap();
+    assert_eq!(sensor.readings, [21, 22, 23]);
+    assert_eq!(sensor.interval_ms, 100);
+    assert_eq!(sensor.calibration, None);
+
+    let err = Sensor::builder().interval_ms(0).name(String::new()).build().unwrap_err();
+    assert_eq!(
+        err,
+        SensorBuilderError::Validation("interval must not be zero".to_string()),
+    );
+    let _: &dyn core::error::Error = &err;
+
+    let err = Board::builder().build().unwrap_err();
+    assert_eq!(err.to_string(), "missing required fields: `sensor.name`");
+}
@@ -0,0 +1,62 @@
+// Setters can be renamed one at a time with `setter(name = "...")`, or all at
+// once with a prefix on the struct, and left out entirely with `setter(skip)`
+// for fields that should always take their default. `vis` narrows who may
+// call the setters of a field, for settings internal to the crate.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug)]
+    #[builder(setter(prefix = "with_"))]
+    pub struct Command {
+        executable: String,
+        #[builder(setter(name = "cwd"))]
+        current_dir: Option<String>,
+        #[builder(each = "arg")]
+        args: Vec<String>,
+        #[builder(setter(skip), default = "3")]
+        retries: u32,
+        #[builder(vis = "pub(crate)", default)]
+        pub(crate) trace: bool,
+        #[builder(setter(skip))]
+        pid: Option<u32>,
+    }
+
+    impl Command {
+        pub fn retries(&self) -> u32 {
+            self.retries
+        }
+
+        pub fn pid(&self) -> Option<u32> {
+            self.pid
+        }
+    }
+
+    pub fn traced() -> CommandBuilder {
+        let mut builder = Command::builder();
+        builder.with_trace(true);
+        builder
+    }
+}
+
+use config::Command;
+
+fn main() {
+    let command = config::traced()
+        .with_executable("cargo".to_owned())
+        .cwd("..".to_owned())
+        .arg("build".to_owned())
+        .extend_args(vec!["--release".to_owned()])
+        .build()
+        .unwrap();
+
+    assert_eq!(command.retries(), 3);
+    assert_eq!(command.pid(), None);
+    assert!(command.trace);
+
+    let command = Command::builder()
+        .with_executable("cargo".to_owned())
+        .build()
+        .unwrap();
+    assert!(!command.trace);
+}
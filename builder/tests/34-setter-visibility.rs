@@ -0,0 +1,43 @@
+// A setter given a narrower visibility is not callable from outside of it, and
+// a skipped field has no setter at all, nor a `clear_<field>()` to unset it.
+// Skipping a field the builder would have no value for is rejected where `skip`
+// is written. A build function given a narrower visibility cannot be called
+// from outside of it either, not even through the hidden one sub-builders use.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder)]
+    pub struct Command {
+        pub executable: String,
+        #[builder(vis = "", default)]
+        trace: bool,
+        #[builder(setter(skip), default)]
+        retries: u32,
+    }
+
+    #[derive(Builder)]
+    #[builder(build_fn(vis = ""))]
+    pub struct Task {
+        pub name: String,
+    }
+
+    #[derive(Builder)]
+    pub struct Server {
+        #[builder(setter(skip))]
+        port: u16,
+    }
+}
+
+fn main() {
+    let mut builder = config::Command::builder();
+    builder.executable("cargo".to_owned());
+    builder.trace(true);
+    builder.retries(3);
+    builder.clear_retries();
+
+    let mut builder = config::Task::builder();
+    builder.name("lint".to_owned());
+    let _ = builder.build();
+    let _ = builder.__build();
+}
@@ -0,0 +1,62 @@
+// The builder type, the function creating it and the one finishing it can all
+// be renamed, for types which already have a `builder()` or whose builder reads
+// better under another name. The error type follows the builder's name.
+
+mod config {
+    use derive_builder::Builder;
+
+    #[derive(Builder, Debug)]
+    #[builder(
+        name = "CommandConfig",
+        constructor = "configure",
+        build_fn(name = "finish", vis = "pub(crate)")
+    )]
+    pub struct Command {
+        pub executable: String,
+        #[builder(each = "arg")]
+        pub args: Vec<String>,
+    }
+
+    impl Command {
+        // Would clash with a generated `builder()`.
+        pub fn builder() -> &'static str {
+            "make"
+        }
+    }
+
+    pub fn cargo() -> Command {
+        Command::configure()
+            .executable("cargo".to_owned())
+            .arg("build".to_owned())
+            .finish()
+            .unwrap()
+    }
+
+    pub fn missing() -> CommandConfigError {
+        Command::configure().finish().unwrap_err()
+    }
+}
+
+#[derive(derive_builder::Builder)]
+#[builder(typestate, name = "PointConfig", build_fn(name = "finish"))]
+pub struct Point {
+    x: i32,
+    y: i32,
+}
+
+fn main() {
+    let command = config::cargo();
+    assert_eq!(command.executable, "cargo");
+    assert_eq!(command.args, vec!["build"]);
+    assert_eq!(config::Command::builder(), "make");
+
+    let _: config::CommandConfig = Default::default();
+    assert_eq!(
+        config::missing(),
+        config::CommandConfigError::MissingFields(vec!["executable".to_owned()]),
+    );
+
+    let point: Point = Point::builder().x(1).y(2).finish();
+    assert_eq!((point.x, point.y), (1, 2));
+    let _: PointConfig<(), ()> = PointConfig::default();
+}
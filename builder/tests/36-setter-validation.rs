@@ -0,0 +1,92 @@
+// Values can be checked as they are set, next to the code setting them, rather
+// than when the whole struct is built.
+//
+// `try_setter` adds a `try_` setter converting its argument with `TryInto`,
+// returning the conversion error. `validate` runs a function on every value
+// given to the setters of the field; its error is returned by the setter as
+// the builder's `InvalidValue`, naming the field. A field with both reports
+// conversion errors the same way.
+
+use derive_builder::Builder;
+use std::num::TryFromIntError;
+
+#[derive(Builder, Debug)]
+#[builder(derive(Debug))]
+pub struct Server {
+    #[builder(validate = "not_empty")]
+    host: String,
+    #[builder(try_setter)]
+    port: u16,
+    #[builder(try_setter, validate = "positive")]
+    workers: Option<u8>,
+}
+
+fn not_empty(host: &String) -> Result<(), &'static str> {
+    if host.is_empty() {
+        Err("must not be empty")
+    } else {
+        Ok(())
+    }
+}
+
+fn positive(workers: &u8) -> Result<(), String> {
+    if *workers == 0 {
+        Err(format!("{} workers cannot serve anything", workers))
+    } else {
+        Ok(())
+    }
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned", derive(Debug))]
+pub struct Limits {
+    #[builder(try_setter)]
+    max: u8,
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let server = Server::builder()
+        .host("localhost".to_owned())?
+        .try_port(8080_i32)?
+        .try_workers(4_u64)?
+        .build()?;
+    assert_eq!(server.port, 8080);
+    assert_eq!(server.workers, Some(4));
+
+    let mut builder = Server::builder();
+    let err = builder.host(String::new()).unwrap_err();
+    assert_eq!(
+        err,
+        ServerBuilderError::InvalidValue {
+            field: "host".to_owned(),
+            reason: "must not be empty".to_owned(),
+        },
+    );
+    assert_eq!(err.to_string(), "invalid value for `host`: must not be empty");
+
+    let err: TryFromIntError = builder.try_port(-1).unwrap_err();
+    assert_eq!(err.to_string(), "out of range integral type conversion attempted");
+
+    let err = builder.workers(0).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for `workers`: 0 workers cannot serve anything",
+    );
+    let err = builder.try_workers(300).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid value for `workers`: out of range integral type conversion attempted",
+    );
+
+    // Rejected values were never stored.
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ServerBuilderError::MissingFields(vec!["host".to_owned(), "port".to_owned()]),
+    );
+
+    let limits = Limits::builder().try_max(255_u32)?.build()?;
+    assert_eq!(limits.max, 255);
+    assert!(Limits::builder().try_max(256_u32).is_err());
+
+    Ok(())
+}
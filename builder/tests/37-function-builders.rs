@@ -0,0 +1,68 @@
+// `#[derive_builder::builder]` on a function generates a builder for its
+// arguments, so that calls with many of them name each one. The builder
+// takes the options of the derive, on the attribute and on the parameters:
+// `connect_builder().host(..).port(..).call()` returns what `connect` does,
+// or the usual error when an argument is missing.
+//
+// Borrowed parameters keep borrowing through the builder, and generic,
+// `async` and typestate functions work too.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+#[derive_builder::builder]
+pub fn connect(
+    host: &str,
+    port: u16,
+    timeout: Option<Duration>,
+    #[builder(each = "header")] headers: BTreeMap<String, String>,
+    #[builder(default = "3")] retries: u32,
+) -> String {
+    format!(
+        "{}:{} {:?} {:?} {}",
+        host, port, timeout, headers, retries,
+    )
+}
+
+#[derive_builder::builder(pattern = "owned", build_fn(name = "run"))]
+fn first<T: Clone>(items: &[T], #[builder(default)] skip: usize) -> Option<&T> {
+    items.get(skip)
+}
+
+#[derive_builder::builder(typestate, constructor = "new_point")]
+fn point(x: i32, y: i32) -> (i32, i32) {
+    (x, y)
+}
+
+#[derive_builder::builder]
+async fn fetch(url: String) -> usize {
+    url.len()
+}
+
+fn main() {
+    let host = String::from("localhost");
+    let connected = connect_builder()
+        .host(&host)
+        .port(8080)
+        .header("accept".to_owned(), "*/*".to_owned())
+        .call()
+        .unwrap();
+    assert_eq!(connected, r#"localhost:8080 None {"accept": "*/*"} 3"#);
+
+    let err = connect_builder().port(8080).call().unwrap_err();
+    assert_eq!(
+        err,
+        ConnectBuilderError::MissingFields(vec!["host".to_owned()]),
+    );
+
+    let items = vec![1, 2, 3];
+    assert_eq!(FirstBuilder::default().items(&items).run(), Ok(Some(&1)));
+    assert_eq!(first_builder().items(&items).skip(2).run(), Ok(Some(&3)));
+
+    assert_eq!(new_point().y(2).x(1).call(), (1, 2));
+
+    let mut builder = fetch_builder();
+    builder.url("https://example.com".to_owned());
+    let future = builder.call();
+    drop(future);
+}
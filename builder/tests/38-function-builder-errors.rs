@@ -0,0 +1,58 @@
+// A builder stores the arguments of its function until it is called, so they
+// all need a name and a type which can be stored. The builder of a method is
+// declared outside of its `impl` block, so `#[builder]` goes on the block too.
+// Holding the receiver, it has no default to start from.
+
+#[derive_builder::builder]
+pub fn draw(_points: impl IntoIterator<Item = (i32, i32)>) {}
+
+#[derive_builder::builder]
+pub fn pair((left, right): (u8, u8)) -> u8 {
+    left + right
+}
+
+#[derive_builder::builder]
+pub fn longest(left: &str, right: &str) -> &str {
+    if left.len() > right.len() {
+        left
+    } else {
+        right
+    }
+}
+
+#[derive_builder::builder(default)]
+pub fn defaulted(_count: u8) {}
+
+pub struct Client;
+
+impl Client {
+    #[derive_builder::builder]
+    pub fn get(&self, _url: String) {}
+}
+
+pub struct Server;
+
+#[derive_builder::builder]
+impl Server {
+    #[builder(pattern = "mutable")]
+    pub fn listen(&self, _port: u16) {}
+
+    #[builder(cli)]
+    pub fn serve(&self, _threads: usize) {}
+}
+
+#[derive_builder::builder]
+impl Iterator for Server {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        None
+    }
+}
+
+#[derive_builder::builder]
+impl Server {
+    pub fn stop(&self) {}
+}
+
+fn main() {}
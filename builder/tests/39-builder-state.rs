@@ -0,0 +1,65 @@
+// A builder can be asked what it holds before `build()` is called:
+// `is_<field>_set()` tells whether a field has a value, and `missing_fields()`
+// lists the required fields `build()` would complain about, in the same
+// order. `clear_<field>()` unsets a field again, and `reset()` the whole
+// builder.
+//
+// The fields still missing from a sub-builder are named through its field,
+// like `server.host`.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Server {
+    host: String,
+    #[builder(default = "80")]
+    port: u16,
+}
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    executable: String,
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    env: Vec<String>,
+    current_dir: Option<String>,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Builder, Debug)]
+#[builder(pattern = "owned")]
+pub struct Point(i32, #[builder(default)] i32);
+
+fn main() {
+    let mut builder = Command::builder();
+    assert!(!builder.is_executable_set());
+    assert_eq!(builder.missing_fields(), ["executable", "env", "server.host"]);
+
+    builder.executable("cargo".to_owned()).arg("build".to_owned());
+    assert!(builder.is_executable_set());
+    assert!(builder.is_args_set());
+    assert!(!builder.is_current_dir_set());
+    assert_eq!(builder.missing_fields(), ["env", "server.host"]);
+
+    builder.env(vec![]).server().host("localhost".to_owned());
+    assert!(builder.missing_fields().is_empty());
+
+    builder.clear_executable().clear_server();
+    assert!(!builder.is_executable_set());
+    assert_eq!(builder.missing_fields(), ["executable", "server.host"]);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        CommandBuilderError::MissingFields(vec!["executable".to_owned(), "server.host".to_owned()]),
+    );
+
+    builder.reset();
+    assert!(!builder.is_args_set());
+    assert_eq!(builder.missing_fields(), ["executable", "env", "server.host"]);
+
+    let builder = Point::builder()._0(1)._1(2);
+    assert!(builder.is_0_set() && builder.is_1_set());
+    let builder = builder.clear_1();
+    assert!(builder.missing_fields().is_empty());
+    assert_eq!(builder.reset().missing_fields(), ["_0"]);
+}
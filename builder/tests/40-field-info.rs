@@ -0,0 +1,126 @@
+// Every builder lists its fields in `FIELDS`, for code which needs to know
+// about them at runtime, like a generator of configuration docs. Each entry
+// gives the field's name, its type and default as written, whether it has to
+// be set, and its doc comment.
+//
+// The table is made of types from `derive_builder`, which generated code
+// refers to by absolute path.
+
+use derive_builder::{Builder, BuilderFieldInfo, BuilderFieldKind};
+use std::collections::HashMap;
+use std::time::Duration;
+
+#[derive(Builder, Debug)]
+pub struct Server {
+    host: String,
+}
+
+#[derive(Builder, Debug)]
+pub struct Command {
+    /// The program to run.
+    executable: String,
+    /// Arguments passed to the program.
+    ///
+    /// In order.
+    #[builder(each = "arg")]
+    args: Vec<String>,
+    #[builder(each = "env")]
+    envs: HashMap<String, String>,
+    current_dir: Option<&'static str>,
+    #[builder(default = "Duration::from_secs(3)")]
+    timeout: std::time::Duration,
+    #[builder(default)]
+    retries: u8,
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Point(i32, #[builder(default = "-1")] i32);
+
+#[derive_builder::builder]
+fn greet(name: &str, greeting: Option<(&str, u8)>) -> String {
+    format!("{:?} {}", greeting, name)
+}
+
+fn main() {
+    let info = |name, ty, kind, default, doc| BuilderFieldInfo {
+        name,
+        ty,
+        kind,
+        default,
+        doc,
+    };
+    assert_eq!(
+        CommandBuilder::FIELDS,
+        [
+            info(
+                "executable",
+                "String",
+                BuilderFieldKind::Required,
+                None,
+                "The program to run.",
+            ),
+            info(
+                "args",
+                "Vec<String>",
+                BuilderFieldKind::Each,
+                None,
+                "Arguments passed to the program.\n\nIn order.",
+            ),
+            info(
+                "envs",
+                "HashMap<String, String>",
+                BuilderFieldKind::Each,
+                None,
+                "",
+            ),
+            info(
+                "current_dir",
+                "Option<&'static str>",
+                BuilderFieldKind::Optional,
+                None,
+                "",
+            ),
+            info(
+                "timeout",
+                "std::time::Duration",
+                BuilderFieldKind::Optional,
+                Some("Duration::from_secs(3)"),
+                "",
+            ),
+            info(
+                "retries",
+                "u8",
+                BuilderFieldKind::Optional,
+                Some("Default::default()"),
+                "",
+            ),
+            info("server", "Server", BuilderFieldKind::SubBuilder, None, ""),
+        ],
+    );
+
+    assert_eq!(
+        PointBuilder::FIELDS,
+        [
+            info("_0", "i32", BuilderFieldKind::Required, None, ""),
+            info("_1", "i32", BuilderFieldKind::Optional, Some("-1"), ""),
+        ],
+    );
+
+    assert_eq!(
+        GreetBuilder::FIELDS,
+        [
+            info("name", "&str", BuilderFieldKind::Required, None, ""),
+            info(
+                "greeting",
+                "Option<(&str, u8)>",
+                BuilderFieldKind::Optional,
+                None,
+                "",
+            ),
+        ],
+    );
+    let _ = greet;
+}
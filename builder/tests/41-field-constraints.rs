@@ -0,0 +1,154 @@
+// Some mistakes only show in how the setters are called: the same field set
+// twice by two pieces of code that each think they own it, or options that
+// only make sense together, or not at all together.
+//
+// `set_once` makes `build()` fail with `SetTwice` when a setter of the field is
+// called while it already has a value, naming the first such field. `requires`
+// makes it fail with `Requires` when the field is set without the other, and
+// `conflicts_with` with `Conflicts` when both are set. A field counts as set
+// once given a value, whether by its setter, a command-line flag or the
+// environment; a default does not count, nor does a switch left off.
+
+use derive_builder::Builder;
+
+#[derive(Builder, Debug)]
+pub struct Connection {
+    #[builder(set_once)]
+    host: String,
+    #[builder(set_once, try_setter)]
+    port: u16,
+    #[builder(requires = "password")]
+    user: Option<String>,
+    password: Option<String>,
+    #[builder(conflicts_with = "password")]
+    token: Option<String>,
+}
+
+#[derive(Builder, Debug)]
+pub struct Deployment {
+    #[builder(sub_builder)]
+    connection: Connection,
+    #[builder(default, set_once)]
+    replicas: u8,
+}
+
+#[derive(Builder, Debug)]
+#[builder(cli, derive(Debug))]
+pub struct Listen {
+    #[builder(set_once)]
+    port: u16,
+    #[builder(requires = "cert")]
+    tls: bool,
+    cert: Option<String>,
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn main() {
+    let connection = Connection::builder()
+        .host("localhost".to_owned())
+        .port(5432)
+        .build()
+        .unwrap();
+    assert_eq!(connection.host, "localhost");
+    assert_eq!(connection.port, 5432);
+    assert_eq!(connection.user, None);
+
+    // The second value is stored, but the builder can no longer build.
+    let mut builder = Connection::builder();
+    builder.host("localhost".to_owned()).port(5432);
+    builder.host("example.com".to_owned());
+    assert!(builder.try_port(5433_i32).is_ok());
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ConnectionBuilderError::SetTwice("host".to_owned()),
+    );
+    assert_eq!(
+        builder.build().unwrap_err().to_string(),
+        "`host` was set more than once",
+    );
+
+    // Clearing a field lets it be set again without the error being
+    // forgotten, unlike starting over.
+    builder.clear_host().host("localhost".to_owned());
+    assert!(builder.build().is_err());
+    builder.reset().host("localhost".to_owned()).port(5432);
+    assert!(builder.build().is_ok());
+
+    let mut builder = Connection::builder();
+    builder
+        .host("localhost".to_owned())
+        .port(5432)
+        .user("admin".to_owned());
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ConnectionBuilderError::Requires {
+            field: "user".to_owned(),
+            required: "password".to_owned(),
+        },
+    );
+    assert_eq!(
+        builder.build().unwrap_err().to_string(),
+        "`user` requires `password` to be set",
+    );
+    builder.password("hunter2".to_owned()).token("abc".to_owned());
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ConnectionBuilderError::Conflicts {
+            field: "token".to_owned(),
+            other: "password".to_owned(),
+        },
+    );
+    assert_eq!(
+        builder.build().unwrap_err().to_string(),
+        "`token` conflicts with `password`, they cannot both be set",
+    );
+    builder.clear_token();
+    assert_eq!(builder.build().unwrap().user.as_deref(), Some("admin"));
+
+    // Errors of a sub-builder name its fields through the parent's.
+    let mut builder = Deployment::builder();
+    builder.replicas(3);
+    builder
+        .connection()
+        .host("localhost".to_owned())
+        .port(5432)
+        .port(5433);
+    assert_eq!(
+        builder.build().unwrap_err(),
+        DeploymentBuilderError::SetTwice("connection.port".to_owned()),
+    );
+    let err = Deployment::builder()
+        .replicas(3)
+        .replicas(4)
+        .build()
+        .unwrap_err();
+    assert_eq!(err, DeploymentBuilderError::SetTwice("replicas".to_owned()));
+
+    let mut builder = ListenBuilder::from_args(args(&["--port", "80", "--port", "8080"]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ListenBuilderError::SetTwice("port".to_owned()),
+    );
+
+    let mut builder = ListenBuilder::from_args(args(&["--port", "80"])).unwrap().unwrap();
+    assert!(!builder.is_tls_set());
+    assert!(!builder.build().unwrap().tls);
+
+    let mut builder = ListenBuilder::from_args(args(&["--port", "443", "--tls"]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        builder.build().unwrap_err(),
+        ListenBuilderError::Requires {
+            field: "tls".to_owned(),
+            required: "cert".to_owned(),
+        },
+    );
+    builder.cert("cert.pem".to_owned());
+    assert!(builder.build().unwrap().tls);
+}
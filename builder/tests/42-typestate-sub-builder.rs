@@ -0,0 +1,20 @@
+// A typestate builder changes type as its fields are set, so it cannot be
+// stored in another builder and handed out by `&mut`. Marking a field of such
+// a type as `sub_builder` is reported at the field's type, and the parts of the
+// builder which cannot point at a field report it at the derive.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate)]
+pub struct Server {
+    host: String,
+}
+
+#[derive(Builder)]
+pub struct Deployment {
+    #[builder(sub_builder)]
+    server: Server,
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+// A typestate builder changes type as its fields are set, so there is no
+// single builder type to deserialize a partial configuration into.
+
+use derive_builder::Builder;
+
+#[derive(Builder)]
+#[builder(typestate, serde)]
+pub struct Server {
+    host: String,
+    port: u16,
+}
+
+fn main() {}
@@ -0,0 +1,103 @@
+// `#[derive_builder::builder]` on an `impl` block generates builders for the
+// methods and associated functions in it marked `#[builder]`, taking the same
+// options as on a free function. The builder is generated next to the block
+// and named after the type and the function: `Cache::new_builder()` returns a
+// `CacheNewBuilder`.
+//
+// The builder of a method is made from its receiver, `cache.get_builder()`,
+// and holds it until `call()` passes it on to the method. Since the receiver
+// moves into the call, it always uses the owned pattern.
+
+use std::collections::BTreeMap;
+
+#[derive(Debug)]
+pub struct Cache<T> {
+    name: String,
+    capacity: usize,
+    entries: BTreeMap<String, T>,
+}
+
+#[derive_builder::builder]
+impl<T: Clone> Cache<T> {
+    #[builder]
+    pub fn new(capacity: usize, #[builder(default = r#""cache".to_owned()"#)] name: String) -> Self {
+        Cache {
+            name,
+            capacity,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    #[builder]
+    pub fn get(&self, key: &str, fallback: Option<T>) -> Option<T> {
+        self.entries.get(key).cloned().or(fallback)
+    }
+
+    #[builder]
+    pub fn peek(&self, key: &str) -> Option<&T> {
+        self.entries.get(key)
+    }
+
+    #[builder(setter(into))]
+    pub fn insert(&mut self, key: String, value: T, #[builder(default)] replace: bool) -> bool {
+        if self.entries.len() >= self.capacity || (!replace && self.entries.contains_key(&key)) {
+            return false;
+        }
+        self.entries.insert(key, value);
+        true
+    }
+
+    #[builder(typestate)]
+    pub fn into_values(self, limit: usize) -> Vec<T> {
+        self.entries.into_values().take(limit).collect()
+    }
+
+    #[builder]
+    pub fn boxed_len(self: Box<Self>, #[builder(default)] extra: usize) -> usize {
+        self.entries.len() + extra
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+fn main() {
+    let mut cache = Cache::<u32>::new_builder().capacity(2).call().unwrap();
+    assert_eq!(cache.name, "cache");
+
+    assert!(cache.insert_builder().key("a").value(1u32).call().unwrap());
+    assert!(!cache.insert_builder().key("a").value(2u32).call().unwrap());
+    assert!(cache
+        .insert_builder()
+        .key("a")
+        .value(3u32)
+        .replace(true)
+        .call()
+        .unwrap());
+    assert!(cache.insert_builder().key("b").value(4u32).call().unwrap());
+    assert!(!cache.insert_builder().key("c").value(5u32).call().unwrap());
+    assert_eq!(cache.len(), 2);
+
+    let builder = cache.insert_builder().key("d");
+    let err = builder.call().unwrap_err();
+    assert_eq!(
+        err,
+        CacheInsertBuilderError::MissingFields(vec!["value".to_owned()]),
+    );
+
+    let get = cache.get_builder().key("z").fallback(0).call().unwrap();
+    assert_eq!(get, Some(0));
+    let peeked = cache.peek_builder().key("a").call().unwrap();
+    assert_eq!(peeked, Some(&3));
+
+    let builder = cache.get_builder().key("a");
+    let builder = builder.reset();
+    assert!(builder.key("b").call().unwrap() == Some(4));
+
+    let boxed = Box::new(Cache::<u32>::new_builder().capacity(1).call().unwrap());
+    assert_eq!(boxed.boxed_len_builder().extra(2).call().unwrap(), 2);
+
+    let values = cache.into_values_builder().limit(1).call();
+    assert_eq!(values, [3]);
+}
@@ -0,0 +1,122 @@
+// Generated code calls trait methods through their traits, like
+// `::core::iter::Iterator::next(..)`, rather than relying on the prelude to
+// bring them into scope. Builders can then be derived in a module without one,
+// whatever options they use.
+
+extern crate alloc;
+
+#[no_implicit_prelude]
+mod config {
+    use ::derive_builder::Builder;
+    use ::core::option::Option;
+    use ::std::collections::BTreeMap;
+    use ::std::string::String;
+    use ::std::vec::Vec;
+
+    /// Runs a program.
+    #[derive(Builder, ::core::fmt::Debug)]
+    #[builder(cli, derive(::core::clone::Clone), build_fn(validate = "Command::check"))]
+    pub struct Command {
+        /// The program to run.
+        #[builder(setter(into), validate = "not_empty")]
+        pub executable: String,
+        /// Arguments passed to the program.
+        #[builder(each = "arg")]
+        pub args: Vec<String>,
+        /// Environment of the program.
+        #[builder(each = "env")]
+        pub env: BTreeMap<String, String>,
+        /// Directory to run in.
+        #[builder(env = "TEST_PRELUDE_DIR", requires = "executable")]
+        pub current_dir: Option<String>,
+        /// Times to retry.
+        #[builder(try_setter, default = "1", set_once)]
+        pub retries: u8,
+        /// Prints what runs.
+        #[builder(conflicts_with = "quiet")]
+        pub verbose: bool,
+        /// Prints nothing.
+        pub quiet: bool,
+    }
+
+    impl Command {
+        fn check(&self) -> ::core::result::Result<(), &'static str> {
+            if self.retries == 0 {
+                return ::core::result::Result::Err("at least one try");
+            }
+            ::core::result::Result::Ok(())
+        }
+    }
+
+    fn not_empty(value: &String) -> ::core::result::Result<(), &'static str> {
+        if value.is_empty() {
+            return ::core::result::Result::Err("must not be empty");
+        }
+        ::core::result::Result::Ok(())
+    }
+
+    #[derive(Builder, ::core::fmt::Debug)]
+    pub struct Job {
+        #[builder(sub_builder)]
+        pub command: Command,
+        #[builder(default)]
+        pub tags: Vec<String>,
+    }
+
+    #[derive(Builder, ::core::fmt::Debug)]
+    #[builder(no_std, pattern = "immutable")]
+    pub struct Point(pub i32, #[builder(default)] pub i32);
+
+    #[derive(Builder, ::core::fmt::Debug)]
+    #[builder(typestate)]
+    pub struct Span {
+        pub start: usize,
+        pub end: usize,
+    }
+
+    #[derive(Builder, ::core::fmt::Debug)]
+    pub enum Shape {
+        Circle { radius: u32 },
+        Square(u32),
+    }
+
+    pub struct Counter {
+        pub total: u32,
+    }
+
+    #[::derive_builder::builder]
+    impl Counter {
+        #[builder]
+        pub fn add(&mut self, amount: u32, #[builder(default = "1")] times: u32) -> u32 {
+            self.total += amount * times;
+            self.total
+        }
+    }
+
+    #[::derive_builder::builder]
+    pub fn area(width: u32, #[builder(default = "1")] height: u32) -> u32 {
+        width * height
+    }
+}
+
+use config::*;
+
+fn main() {
+    let mut builder = Job::builder();
+    assert_eq!(builder.build().unwrap_err().to_string(), "missing required fields: `command.executable`");
+    let args = ["--executable", "cargo", "--arg", "build"].map(String::from);
+    *builder.command() = CommandBuilder::from_args(args).unwrap().unwrap();
+    let job = builder.build().unwrap();
+    assert_eq!(job.command.args, ["build"]);
+    assert_eq!(job.command.retries, 1);
+
+    let point = Point::builder()._0(1).build().unwrap();
+    assert_eq!((point.0, point.1), (1, 0));
+    let span = Span::builder().start(1).end(2).build();
+    assert_eq!(span.end - span.start, 1);
+    assert!(Shape::square_builder().build().is_err());
+    assert!(Shape::circle_builder().radius(1).build().is_ok());
+    assert_eq!(area_builder().width(3).call().unwrap(), 3);
+    let mut counter = Counter { total: 0 };
+    assert_eq!(counter.add_builder().amount(2).times(3).call().unwrap(), 6);
+}
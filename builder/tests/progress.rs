@@ -9,5 +9,39 @@ fn tests() {
     t.pass("tests/06-optional-field.rs");
     t.pass("tests/07-repeated-field.rs");
     t.compile_fail("tests/08-unrecognized-attribute.rs");
-    //t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/09-redefined-prelude-types.rs");
+    t.pass("tests/10-typestate.rs");
+    t.compile_fail("tests/11-typestate-missing-field.rs");
+    t.pass("tests/12-missing-fields-error.rs");
+    t.pass("tests/13-default-values.rs");
+    t.pass("tests/14-builder-patterns.rs");
+    t.compile_fail("tests/15-typestate-pattern.rs");
+    t.pass("tests/16-generics.rs");
+    t.pass("tests/17-tuple-structs-and-enums.rs");
+    t.pass("tests/18-setter-into-strip-option.rs");
+    t.pass("tests/19-build-validation.rs");
+    t.pass("tests/20-unrelated-attributes.rs");
+    t.compile_fail("tests/21-malformed-attributes.rs");
+    t.pass("tests/22-each-collections.rs");
+    t.pass("tests/23-extend-setters.rs");
+    t.compile_fail("tests/24-setter-name-clash.rs");
+    t.pass("tests/25-sub-builders.rs");
+    t.pass("tests/26-builder-derives.rs");
+    t.pass("tests/27-merge.rs");
+    t.pass("tests/29-env-fallback.rs");
+    t.pass("tests/30-cli.rs");
+    t.pass("tests/31-to-builder.rs");
+    t.pass("tests/32-no-std.rs");
+    t.pass("tests/33-setter-control.rs");
+    t.compile_fail("tests/34-setter-visibility.rs");
+    t.pass("tests/35-custom-names.rs");
+    t.pass("tests/36-setter-validation.rs");
+    t.pass("tests/37-function-builders.rs");
+    t.compile_fail("tests/38-function-builder-errors.rs");
+    t.pass("tests/39-builder-state.rs");
+    t.pass("tests/40-field-info.rs");
+    t.pass("tests/41-field-constraints.rs");
+    t.compile_fail("tests/42-typestate-sub-builder.rs");
+    t.pass("tests/44-method-builders.rs");
+    t.pass("tests/45-no-implicit-prelude.rs");
 }
@@ -0,0 +1,6 @@
+#[test]
+fn tests() {
+    let t = trybuild::TestCases::new();
+    t.pass("tests/28-serde.rs");
+    t.compile_fail("tests/43-serde-typestate.rs");
+}
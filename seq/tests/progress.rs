@@ -1,4 +1,5 @@
 #[test]
+#[allow(unused_variables)]
 fn tests() {
     let t = trybuild::TestCases::new();
     //t.pass("tests/01-parse-header.rs");